@@ -0,0 +1,187 @@
+use syn::{Error, Ident, Lit, LitStr, Meta, NestedMeta};
+
+const ATTR: &str = "serde_partial";
+
+/// `#[serde_partial(...)]` attributes on a container.
+#[derive(Default)]
+pub struct Container {
+    pub fields_name: Option<Ident>,
+    pub filter_name: Option<Ident>,
+    pub codec: bool,
+    pub generate_accessor_fn: bool,
+    pub serde_fields: bool,
+    pub builder: bool,
+}
+
+impl Container {
+    pub fn from_ast(input: &syn::DeriveInput) -> Result<Self, Error> {
+        let mut attrs = Self::default();
+
+        for meta in metas(&input.attrs)? {
+            match &meta {
+                Meta::NameValue(nv) if nv.path.is_ident("fields_name") => {
+                    attrs.fields_name = Some(lit_str(&nv.lit)?.parse()?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("filter_name") => {
+                    attrs.filter_name = Some(lit_str(&nv.lit)?.parse()?);
+                }
+                Meta::Path(path) if path.is_ident("codec") => {
+                    attrs.codec = true;
+                }
+                Meta::Path(path) if path.is_ident("generate_accessor_fn") => {
+                    attrs.generate_accessor_fn = true;
+                }
+                Meta::Path(path) if path.is_ident("serde_fields") => {
+                    attrs.serde_fields = true;
+                }
+                Meta::Path(path) if path.is_ident("builder") => {
+                    attrs.builder = true;
+                }
+                _ => return Err(unknown(&meta)),
+            }
+        }
+
+        Ok(attrs)
+    }
+}
+
+/// `#[serde_partial(...)]` attributes on a field.
+#[derive(Default)]
+pub struct Field {
+    pub sql_column: Option<LitStr>,
+    /// `Some(None)` if the field itself is the nested type, `Some(Some(ty))` if it contains values of type `ty`.
+    pub nested: Option<Option<syn::Type>>,
+    pub always: bool,
+    pub never: bool,
+    pub groups: Vec<Ident>,
+    pub since: Option<u32>,
+    pub removed_in: Option<u32>,
+    pub requires: Vec<Ident>,
+    /// Alternative names which select the field, along with the identifiers of their `Fields` members.
+    pub aliases: Vec<(Ident, LitStr)>,
+}
+
+impl Field {
+    pub fn from_ast(field: &syn::Field) -> Result<Self, Error> {
+        let mut attrs = Self::default();
+
+        for meta in metas(&field.attrs)? {
+            match &meta {
+                Meta::NameValue(nv) if nv.path.is_ident("sql_column") => {
+                    attrs.sql_column = Some(lit_str(&nv.lit)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("group") => {
+                    let group: Ident = lit_str(&nv.lit)?.parse()?;
+                    if attrs.groups.contains(&group) {
+                        return Err(Error::new_spanned(&nv.lit, "duplicate group"));
+                    }
+                    attrs.groups.push(group);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("requires") => {
+                    let permission: Ident = lit_str(&nv.lit)?.parse()?;
+                    if attrs.requires.contains(&permission) {
+                        return Err(Error::new_spanned(&nv.lit, "duplicate permission"));
+                    }
+                    attrs.requires.push(permission);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("alias") => {
+                    let alias = lit_str(&nv.lit)?;
+                    let ident: Ident = alias.parse().map_err(|_| {
+                        Error::new_spanned(&nv.lit, "alias must be a valid identifier")
+                    })?;
+                    if attrs
+                        .aliases
+                        .iter()
+                        .any(|(_, a)| a.value() == alias.value())
+                    {
+                        return Err(Error::new_spanned(&nv.lit, "duplicate alias"));
+                    }
+                    attrs.aliases.push((ident, alias));
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("since") => {
+                    attrs.since = Some(lit_int(&nv.lit)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("removed_in") => {
+                    attrs.removed_in = Some(lit_int(&nv.lit)?);
+                }
+                Meta::Path(path) if path.is_ident("always") => {
+                    attrs.always = true;
+                }
+                Meta::Path(path) if path.is_ident("never") => {
+                    attrs.never = true;
+                }
+                Meta::Path(path) if path.is_ident("nested") => {
+                    attrs.nested = Some(None);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("nested") => {
+                    attrs.nested = Some(Some(lit_str(&nv.lit)?.parse()?));
+                }
+                _ => return Err(unknown(&meta)),
+            }
+        }
+
+        if attrs.always && attrs.never {
+            return Err(Error::new_spanned(
+                field,
+                "a field can't be both `always` and `never` serialized",
+            ));
+        }
+
+        if let (Some(since), Some(removed_in)) = (attrs.since, attrs.removed_in) {
+            if removed_in <= since {
+                return Err(Error::new_spanned(
+                    field,
+                    "a field must be removed in a later version than the one it was added in",
+                ));
+            }
+        }
+
+        Ok(attrs)
+    }
+}
+
+fn metas(attrs: &[syn::Attribute]) -> Result<Vec<Meta>, Error> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident(ATTR)) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(Error::new_spanned(
+                                lit,
+                                "expected attribute, found literal",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected attribute arguments in parentheses: #[serde_partial(...)]",
+                ))
+            }
+        }
+    }
+    Ok(metas)
+}
+
+fn lit_str(lit: &Lit) -> Result<LitStr, Error> {
+    match lit {
+        Lit::Str(s) => Ok(s.clone()),
+        _ => Err(Error::new_spanned(lit, "expected string literal")),
+    }
+}
+
+fn lit_int(lit: &Lit) -> Result<u32, Error> {
+    match lit {
+        Lit::Int(i) => i.base10_parse(),
+        _ => Err(Error::new_spanned(lit, "expected integer literal")),
+    }
+}
+
+fn unknown(meta: &Meta) -> Error {
+    Error::new_spanned(meta, "unknown serde_partial attribute")
+}
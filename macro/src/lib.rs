@@ -1,21 +1,28 @@
 use proc_macro::{Span, TokenStream};
 use quote::ToTokens;
 use serde_derive_internals::{
-    ast::{Container, Data, Style},
+    ast::{Container, Data, Style, Variant},
+    attr::TagType,
     Ctxt, Derive,
 };
-use syn::{DeriveInput, Error};
+use syn::ext::IdentExt;
+use syn::{DeriveInput, Error, GenericParam};
 
-#[proc_macro_derive(SerializePartial, attributes(serde))]
+mod attr;
+
+#[proc_macro_derive(SerializePartial, attributes(serde, serde_partial))]
 pub fn serialize_partial(input: TokenStream) -> TokenStream {
     let cx = Ctxt::new();
-    let item = syn::parse_macro_input!(input as DeriveInput);
+    let mut item = syn::parse_macro_input!(input as DeriveInput);
+    if let Err(err) = expand_rename_all_fields(&mut item) {
+        return err.to_compile_error().into();
+    }
     let Container {
         data,
         attrs,
         ident,
+        generics,
         original,
-        ..
     } = match Container::from_ast(&cx, &item, Derive::Serialize) {
         Some(c) => c,
         None => return item.to_token_stream().into(),
@@ -27,22 +34,161 @@ pub fn serialize_partial(input: TokenStream) -> TokenStream {
         return item.to_token_stream().into();
     }
 
+    // internally tagged enums serialize their tag as the first field of every variant,
+    // so it's parsed as an extra field which is always serialized
+    let tag_item = match attrs.tag() {
+        TagType::Internal { tag } => Some(tag_item(tag)),
+        _ => None,
+    };
+    let tag_cx = Ctxt::new();
+    let tag_field = tag_item
+        .as_ref()
+        .and_then(|item| Container::from_ast(&tag_cx, item, Derive::Serialize))
+        .and_then(|c| match c.data {
+            Data::Struct(_, mut fields) => fields.pop(),
+            Data::Enum(_) => None,
+        });
+    if let Err(errors) = tag_cx.check() {
+        return errors
+            .iter()
+            .map(Error::to_compile_error)
+            .collect::<proc_macro2::TokenStream>()
+            .into();
+    }
+
+    let is_enum = matches!(data, Data::Enum(_));
     let mut fields = match data {
         Data::Struct(Style::Struct, f) => f,
-        _ => {
+        Data::Struct(Style::Newtype, f) => {
+            if let Err(err) = attr::Container::from_ast(original) {
+                return err.to_compile_error().into();
+            }
+            return newtype(ident, generics, &f[0]).into();
+        }
+        Data::Struct(Style::Tuple, _) => return Error::new(
+            Span::call_site().into(),
+            "SerializePartial doesn't support tuple structs, consider using named fields instead",
+        )
+        .to_compile_error()
+        .into(),
+        Data::Struct(Style::Unit, _) => {
             return Error::new(
                 Span::call_site().into(),
-                "SerializePartial only supports structs",
+                "SerializePartial doesn't support unit structs since they have no fields",
             )
             .to_compile_error()
             .into()
         }
+        Data::Enum(variants) => match enum_fields(&attrs, variants, tag_field) {
+            Ok(fields) => fields,
+            Err(err) => return err.to_compile_error().into(),
+        },
     };
-    for f in fields.iter_mut() {
-        f.attrs.rename_by_rules(attrs.rename_all_rules());
-    }
+    // field names are already renamed according to the container or variant rules
+    let skipped_names = fields
+        .iter()
+        .filter(|f| f.attrs.skip_serializing())
+        .map(|f| f.attrs.name().serialize_name())
+        .collect::<Vec<_>>();
     fields.retain(|f| !f.attrs.skip_serializing());
 
+    // serde serializes flattened fields as entries of the parent map,
+    // which are selected through the fields and filter of the flattened type
+    let flattened = fields
+        .iter()
+        .filter(|f| f.attrs.flatten())
+        .map(|f| {
+            let ident = f.original.ident.as_ref().unwrap();
+            (ident, quote::format_ident!("__{}_flat", ident), f.ty)
+        })
+        .collect::<Vec<_>>();
+    fields.retain(|f| !f.attrs.flatten());
+    let flat_idents = flattened.iter().map(|f| f.0).collect::<Vec<_>>();
+    let flat_idents = &flat_idents;
+    let flat_filter_idents = flattened.iter().map(|f| &f.1).collect::<Vec<_>>();
+    let flat_filter_idents = &flat_filter_idents;
+    let flat_tys = flattened.iter().map(|f| f.2).collect::<Vec<_>>();
+    let flat_tys = &flat_tys;
+
+    let all_partial_attrs = match fields
+        .iter()
+        .map(|f| attr::Field::from_ast(f.original))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // serde serializes or skips these fields in order, which makes their positions usable as indices
+    let positional_names = fields
+        .iter()
+        .map(|f| f.attrs.name().serialize_name())
+        .collect::<Vec<_>>();
+    let positional_names = &positional_names;
+    let positional_skips = fields
+        .iter()
+        .zip(&all_partial_attrs)
+        .map(|(f, a)| {
+            if a.never {
+                quote::quote!(true)
+            } else if a.always {
+                quote::quote!(false)
+            } else {
+                let ident = f.original.ident.as_ref().unwrap();
+                quote::quote!(!self.#ident)
+            }
+        })
+        .collect::<Vec<_>>();
+    let positional_skips = &positional_skips;
+    let positions = 0..positional_names.len();
+
+    // fields which are never serialized by partials aren't part of the selectable fields
+    let mut never_names = Vec::new();
+    let mut partial_attrs = Vec::new();
+    let fields = fields
+        .into_iter()
+        .zip(all_partial_attrs)
+        .filter_map(|(f, a)| {
+            if a.never {
+                never_names.push(f.attrs.name().serialize_name());
+                None
+            } else {
+                partial_attrs.push(a);
+                Some(f)
+            }
+        })
+        .collect::<Vec<_>>();
+    let never_names = &never_names;
+
+    let always_names = fields
+        .iter()
+        .zip(&partial_attrs)
+        .filter(|(_, a)| a.always)
+        .map(|(f, _)| f.attrs.name().serialize_name())
+        .collect::<Vec<_>>();
+    let always_names = &always_names;
+    let always_len = always_names.len();
+    let optional_idents = fields
+        .iter()
+        .zip(&partial_attrs)
+        .filter(|(_, a)| !a.always)
+        .map(|(f, _)| f.original.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let optional_idents = &optional_idents;
+
+    let skip_exprs = fields
+        .iter()
+        .zip(&partial_attrs)
+        .map(|(f, a)| {
+            if a.always {
+                quote::quote!(false)
+            } else {
+                let ident = f.original.ident.as_ref().unwrap();
+                quote::quote!(!self.#ident)
+            }
+        })
+        .collect::<Vec<_>>();
+
     let field_idents = fields
         .iter()
         .map(|f| f.original.ident.as_ref().unwrap())
@@ -55,29 +201,205 @@ pub fn serialize_partial(input: TokenStream) -> TokenStream {
         .collect::<Vec<_>>();
     let field_names = &field_names;
 
+    let rust_names = field_idents
+        .iter()
+        .map(|i| i.unraw().to_string())
+        .collect::<Vec<_>>();
+    let rust_names = &rust_names;
+
+    let type_names = fields.iter().map(|f| type_name(f.ty)).collect::<Vec<_>>();
+    let type_names = &type_names;
+
     let fields_len = fields.len();
+    let field_indices = (0..fields_len).collect::<Vec<_>>();
 
-    let fields_struct_ident = &quote::format_ident!("{}Fields", ident);
-    let filter_struct_ident = &quote::format_ident!("{}Filter", ident);
+    // aliases are extra `Fields` members which select the same field as their target
+    let mut alias_names: Vec<String> = Vec::new();
+    let mut aliases = Vec::new();
+    for (i, attrs) in partial_attrs.iter().enumerate() {
+        for (alias_ident, alias) in &attrs.aliases {
+            let name = alias.value();
+            if field_names.contains(&name)
+                || never_names.contains(&name)
+                || alias_names.contains(&name)
+            {
+                return Error::new_spanned(alias, "alias conflicts with another field name")
+                    .to_compile_error()
+                    .into();
+            }
+            alias_names.push(name);
+            aliases.push((alias_ident, i));
+        }
+    }
+    let alias_names = &alias_names;
+    let alias_idents = aliases.iter().map(|a| a.0).collect::<Vec<_>>();
+    let alias_idents = &alias_idents;
+    let alias_targets = aliases
+        .iter()
+        .map(|a| field_idents[a.1])
+        .collect::<Vec<_>>();
+    let alias_targets = &alias_targets;
+    let alias_rust_names = aliases.iter().map(|a| &rust_names[a.1]);
+    let alias_type_names = aliases.iter().map(|a| &type_names[a.1]);
+    let alias_indices = aliases.iter().map(|a| a.1);
+    let alias_skip_exprs = aliases.iter().map(|a| &skip_exprs[a.1]);
+
+    let container_attrs = match attr::Container::from_ast(original) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let fields_struct_ident = &container_attrs
+        .fields_name
+        .unwrap_or_else(|| quote::format_ident!("{}Fields", ident));
+    let filter_struct_ident = &container_attrs
+        .filter_name
+        .unwrap_or_else(|| quote::format_ident!("{}Filter", ident));
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ty = &quote::quote!(#ident #ty_generics);
+
+    let partial_lifetime: syn::Lifetime = syn::parse_quote!('__a);
+    let partial_generics = partial_generics(generics, ty, &partial_lifetime);
+    let (partial_impl_generics, _, partial_where_clause) = partial_generics.split_for_impl();
+
+    let mut groups: Vec<(&syn::Ident, Vec<&syn::Ident>)> = Vec::new();
+    for (ident, attrs) in field_idents.iter().zip(&partial_attrs) {
+        for group in &attrs.groups {
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, idents)) => idents.push(ident),
+                None => groups.push((group, vec![ident])),
+            }
+        }
+    }
+    let group_methods = groups.iter().map(|(group, idents)| {
+        let len = idents.len();
+        quote::quote! {
+            pub fn #group<'__f>(self) -> [::serde_partial::Field<'__f, #ty>; #len] {
+                [
+                    #(
+                        self.#idents,
+                    )*
+                ]
+            }
+        }
+    });
+
+    let versions_method = if partial_attrs
+        .iter()
+        .any(|a| a.since.is_some() || a.removed_in.is_some())
+    {
+        let sinces = partial_attrs.iter().map(|a| a.since.unwrap_or(0));
+        let (removed_idents, removed_versions): (Vec<&syn::Ident>, Vec<u32>) = field_idents
+            .iter()
+            .zip(&partial_attrs)
+            .filter_map(|(i, a)| a.removed_in.map(|v| (*i, v)))
+            .unzip();
+        quote::quote! {
+            pub fn versions(&self) -> ::serde_partial::filter::VersionFilter<#ty> {
+                ::serde_partial::filter::VersionFilter::new()
+                    #(
+                        .added(#sinces, [self.#field_idents])
+                    )*
+                    #(
+                        .removed(#removed_versions, [self.#removed_idents])
+                    )*
+            }
+        }
+    } else {
+        quote::quote!()
+    };
 
     let fields_struct = quote::quote! {
-        #[derive(Debug, Clone, Copy)]
-        #vis struct #fields_struct_ident {
+        #vis struct #fields_struct_ident #generics #where_clause {
+            #(
+                pub #field_idents: ::serde_partial::Field<'static, #ty>,
+            )*
             #(
-                pub #field_idents: ::serde_partial::Field<'static, #ident>,
+                pub #alias_idents: ::serde_partial::Field<'static, #ty>,
+            )*
+            #(
+                pub #flat_idents: <#flat_tys as ::serde_partial::SerializePartial<'static>>::Fields,
             )*
         }
 
-        impl #fields_struct_ident {
+        impl #impl_generics #fields_struct_ident #ty_generics #where_clause {
             pub const FIELDS: Self = Self {
                 #(
-                    #field_idents: ::serde_partial::Field::new(#field_names),
+                    #field_idents: ::serde_partial::Field::new(#field_names)
+                        .with_rust_name(#rust_names)
+                        .with_type(#type_names)
+                        .with_index(#field_indices),
+                )*
+                #(
+                    #alias_idents: ::serde_partial::Field::new(#alias_names)
+                        .with_rust_name(#alias_rust_names)
+                        .with_type(#alias_type_names)
+                        .with_index(#alias_indices),
+                )*
+                #(
+                    #flat_idents: <<#flat_tys as ::serde_partial::SerializePartial<'static>>::Fields>::FIELDS,
                 )*
             };
+
+            pub const FIELD_NAMES: &'static [&'static str] = &[#(#field_names),*];
+
+            pub const FIELD_COUNT: usize = #fields_len;
+
+            pub const SKIPPED_NAMES: &'static [&'static str] = &[#(#skipped_names),*];
+
+            pub fn names(&self) -> ::core::iter::Copied<::core::slice::Iter<'static, &'static str>> {
+                Self::FIELD_NAMES.iter().copied()
+            }
+
+            pub const fn len(&self) -> usize {
+                Self::FIELD_COUNT
+            }
+
+            pub const fn is_empty(&self) -> bool {
+                Self::FIELD_COUNT == 0
+            }
+
+            pub fn contains(&self, name: &str) -> bool {
+                Self::FIELD_NAMES.contains(&name)
+            }
+
+            pub fn skipped_names() -> &'static [&'static str] {
+                Self::SKIPPED_NAMES
+            }
+
+            #(
+                #group_methods
+            )*
+
+            #versions_method
+        }
+
+        impl #impl_generics ::core::fmt::Debug for #fields_struct_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(::core::stringify!(#fields_struct_ident))
+                    #(
+                        .field(::core::stringify!(#field_idents), &self.#field_idents)
+                    )*
+                    #(
+                        .field(::core::stringify!(#alias_idents), &self.#alias_idents)
+                    )*
+                    #(
+                        .field(::core::stringify!(#flat_idents), &self.#flat_idents)
+                    )*
+                    .finish()
+            }
         }
 
-        impl ::core::iter::IntoIterator for #fields_struct_ident {
-            type Item = ::serde_partial::Field<'static, #ident>;
+        impl #impl_generics ::core::clone::Clone for #fields_struct_ident #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+        impl #impl_generics ::core::marker::Copy for #fields_struct_ident #ty_generics #where_clause {}
+
+        impl #impl_generics ::core::iter::IntoIterator for #fields_struct_ident #ty_generics #where_clause {
+            type Item = ::serde_partial::Field<'static, #ty>;
             type IntoIter = ::core::array::IntoIter<Self::Item, #fields_len>;
 
             fn into_iter(self) -> Self::IntoIter {
@@ -91,74 +413,836 @@ pub fn serialize_partial(input: TokenStream) -> TokenStream {
         }
     };
 
+    let nested = fields
+        .iter()
+        .zip(&partial_attrs)
+        .filter_map(|(f, a)| {
+            let ty = match &a.nested {
+                Some(Some(ty)) => ty,
+                Some(None) => f.ty,
+                None => return None,
+            };
+            let ident = f.original.ident.as_ref().unwrap();
+            Some((
+                ident,
+                quote::format_ident!("__{}_nested", ident),
+                f.attrs.name().serialize_name(),
+                ty,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let nested_idents = nested.iter().map(|n| n.0).collect::<Vec<_>>();
+    let nested_idents = &nested_idents;
+    let nested_filter_idents = nested.iter().map(|n| &n.1).collect::<Vec<_>>();
+    let nested_filter_idents = &nested_filter_idents;
+    let nested_names = nested.iter().map(|n| &n.2).collect::<Vec<_>>();
+    let nested_names = &nested_names;
+    let nested_tys = nested.iter().map(|n| n.3).collect::<Vec<_>>();
+    let nested_tys = &nested_tys;
+
+    // fields with a `skip_serializing_if` predicate might be skipped regardless of the filter
+    // the fields of enums depend on the variant
+    // serde doesn't provide a length for structs with flattened fields
+    let filtered_len = if is_enum
+        || !flattened.is_empty()
+        || fields
+            .iter()
+            .any(|f| f.attrs.skip_serializing_if().is_some())
+    {
+        quote::quote!(::core::option::Option::None)
+    } else {
+        quote::quote! {
+            let mut len = #always_len;
+            #(
+                if self.#optional_idents {
+                    len += 1;
+                }
+            )*
+            ::core::option::Option::Some(len)
+        }
+    };
+
+    // nested and flattened filters aren't necessarily `Copy`
+    let filter_derives = if nested.is_empty() && flattened.is_empty() {
+        quote::quote!(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)
+    } else {
+        quote::quote!(Debug, Default, Clone, PartialEq, Eq, Hash)
+    };
+
+    // the positions of enum fields depend on the variant
+    let skip_at = if is_enum {
+        quote::quote!()
+    } else {
+        quote::quote! {
+            fn skip_at(&self, field: ::serde_partial::Field<'_, #ty>, position: usize, human_readable: bool) -> bool {
+                const NAMES: &[&str] = &[#(#positional_names),*];
+                match NAMES.get(position) {
+                    // serde passes the same literals, so comparing pointers usually avoids comparing names
+                    ::core::option::Option::Some(name) if ::core::ptr::eq(*name, field.name()) || *name == field.name() => match position {
+                        #(
+                            #positions => #positional_skips,
+                        )*
+                        _ => ::core::unreachable!(),
+                    },
+                    _ => ::serde_partial::SerializeFilter::<#ty>::skip_hr(self, field, human_readable),
+                }
+            }
+        }
+    };
+
+    let mut permissions: Vec<&syn::Ident> = Vec::new();
+    for attrs in &partial_attrs {
+        for permission in &attrs.requires {
+            if !permissions.contains(&permission) {
+                if permissions.len() == 64 {
+                    return Error::new_spanned(
+                        permission,
+                        "too many permissions, at most 64 are supported",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                permissions.push(permission);
+            }
+        }
+    }
+    let permissions_impl = if permissions.is_empty() {
+        quote::quote!()
+    } else {
+        let permission_bits = 0..permissions.len() as u32;
+        let includes = partial_attrs.iter().map(|a| {
+            let required = a
+                .requires
+                .iter()
+                .map(|p| 1u64 << permissions.iter().position(|q| q == &p).unwrap())
+                .fold(0, |mask, bit| mask | bit);
+            if required == 0 {
+                quote::quote!(true)
+            } else {
+                quote::quote!(mask & #required == #required)
+            }
+        });
+        quote::quote! {
+            impl #filter_struct_ident {
+                #(
+                    pub const #permissions: u64 = 1 << #permission_bits;
+                )*
+
+                pub fn for_permissions(mask: u64) -> Self {
+                    let mut filter = <Self as ::core::default::Default>::default();
+                    #(
+                        filter.#field_idents = #includes;
+                    )*
+                    filter
+                }
+            }
+        }
+    };
+
+    let mut array_generics = generics.clone();
+    array_generics
+        .params
+        .push(syn::parse_quote!(const __N: usize));
+    let (array_impl_generics, _, _) = array_generics.split_for_impl();
+
     let filter_struct = quote::quote! {
-        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        #[derive(#filter_derives)]
         #vis struct #filter_struct_ident {
             #(
                 #field_idents: bool,
             )*
+            #(
+                #nested_filter_idents: ::core::option::Option<::serde_partial::filter::NestedFilter<
+                    #nested_tys,
+                    <#nested_tys as ::serde_partial::SerializePartial<'static>>::Filter,
+                >>,
+            )*
+            #(
+                #flat_filter_idents: <#flat_tys as ::serde_partial::SerializePartial<'static>>::Filter,
+            )*
         }
 
-        impl ::serde_partial::SerializeFilter<#ident> for #filter_struct_ident {
-            fn skip(&self, field: ::serde_partial::Field<'_, #ident>) -> bool {
+        impl #impl_generics ::serde_partial::SerializeFilter<#ty> for #filter_struct_ident #where_clause {
+            fn skip(&self, field: ::serde_partial::Field<'_, #ty>) -> bool {
                 match field.name() {
                     #(
-                        #field_names => !self.#field_idents,
+                        #field_names => #skip_exprs,
                     )*
-                    _ => panic!("unknown field"),
+                    #(
+                        #never_names => true,
+                    )*
+                    #(
+                        #alias_names => #alias_skip_exprs,
+                    )*
+                    name => {
+                        #(
+                            if <#flat_tys as ::serde_partial::SerializePartial<'static>>::field_names().contains(&name) {
+                                return ::serde_partial::SerializeFilter::<#flat_tys>::skip(&self.#flat_filter_idents, field.cast());
+                            }
+                        )*
+                        ::core::panic!(
+                            "unknown field `{}` in `{}`",
+                            name,
+                            ::core::any::type_name::<#ty>(),
+                        )
+                    }
                 }
             }
 
             fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
-                let mut len = 0;
+                #filtered_len
+            }
+
+            #skip_at
+
+            fn exact_len(&self) -> bool {
+                true
+            }
+
+            fn nested(&self, field: ::serde_partial::Field<'_, #ty>) -> ::core::option::Option<&dyn ::serde_partial::filter::ErasedFilter> {
+                match field.name() {
+                    #(
+                        #nested_names => self.#nested_filter_idents
+                            .as_ref()
+                            .map(|f| f as &dyn ::serde_partial::filter::ErasedFilter),
+                    )*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl ::serde_partial::filter::SelectFilter for #filter_struct_ident {
+            fn select<'a>(&mut self, name: &'a str) -> ::core::result::Result<(), ::serde_partial::UnknownFieldError<'a>> {
+                #filter_struct_ident::select(self, name)
+            }
+        }
+
+        impl #filter_struct_ident {
+            pub fn select<'a>(&mut self, name: &'a str) -> ::core::result::Result<(), ::serde_partial::UnknownFieldError<'a>> {
+                match name {
+                    #(
+                        #field_names => {
+                            self.#field_idents = true;
+                            return ::core::result::Result::Ok(());
+                        }
+                    )*
+                    #(
+                        #alias_names => {
+                            self.#alias_targets = true;
+                            return ::core::result::Result::Ok(());
+                        }
+                    )*
+                    _ => {}
+                }
+                #(
+                    if let ::core::option::Option::Some(path) = name.strip_prefix(::core::concat!(#nested_names, ".")) {
+                        self.#nested_idents = true;
+                        return self.#nested_filter_idents
+                            .get_or_insert_with(::core::default::Default::default)
+                            .get_mut()
+                            .select(path)
+                            .map_err(|err| ::serde_partial::UnknownFieldError::with_expected(name, err.expected()));
+                    }
+                )*
                 #(
-                    if self.#field_idents {
-                        len += 1;
+                    if ::serde_partial::filter::SelectFilter::select(&mut self.#flat_filter_idents, name).is_ok() {
+                        return ::core::result::Result::Ok(());
                     }
                 )*
-                Some(len)
+                ::core::result::Result::Err(::serde_partial::UnknownFieldError::with_expected(name, &[#(#field_names),*]))
+            }
+
+            pub fn from_names<'a, I>(names: I) -> ::core::result::Result<Self, ::serde_partial::UnknownFieldError<'a>>
+            where
+                I: ::core::iter::IntoIterator<Item = &'a str>,
+            {
+                let mut filter = <Self as ::core::default::Default>::default();
+
+                for name in names {
+                    filter.select(name)?;
+                }
+
+                ::core::result::Result::Ok(filter)
+            }
+
+            pub fn from_args<'a>(args: &[&'a str]) -> ::core::result::Result<Self, ::serde_partial::UnknownFieldError<'a>> {
+                Self::from_names(
+                    args.iter()
+                        .flat_map(|arg| arg.split(','))
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty()),
+                )
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#fields_struct_ident #ty_generics> for #filter_struct_ident #where_clause {
+            fn from(_fields: #fields_struct_ident #ty_generics) -> Self {
+                let mut filter = <Self as ::core::default::Default>::default();
+                #(
+                    filter.#field_idents = true;
+                )*
+                #(
+                    filter.#flat_filter_idents = ::core::convert::From::from(_fields.#flat_idents);
+                )*
+                filter
             }
         }
+
+        impl #array_impl_generics ::core::convert::From<[::serde_partial::Field<'static, #ty>; __N]> for #filter_struct_ident #where_clause {
+            fn from(fields: [::serde_partial::Field<'static, #ty>; __N]) -> Self {
+                let mut filter = <Self as ::core::default::Default>::default();
+                for field in fields.iter() {
+                    if let ::core::result::Result::Err(err) = filter.select(field.name()) {
+                        ::core::panic!("{} in `{}`", err, ::core::any::type_name::<#ty>());
+                    }
+                }
+                filter
+            }
+        }
+
+        impl<'__n> ::core::convert::TryFrom<&[&'__n str]> for #filter_struct_ident {
+            type Error = ::serde_partial::UnknownFieldError<'__n>;
+
+            fn try_from(names: &[&'__n str]) -> ::core::result::Result<Self, Self::Error> {
+                Self::from_names(names.iter().copied())
+            }
+        }
+
+        #permissions_impl
+    };
+
+    let field_count = if flattened.is_empty() {
+        quote::quote!(::core::option::Option::Some(#fields_len))
+    } else {
+        quote::quote!(::core::option::Option::None)
     };
 
     let trait_impl = quote::quote! {
-        impl<'a> ::serde_partial::SerializePartial<'a> for #ident {
-            type Fields = #fields_struct_ident;
+        impl #partial_impl_generics ::serde_partial::SerializePartial<#partial_lifetime> for #ty #partial_where_clause {
+            type Fields = #fields_struct_ident #ty_generics;
             type Filter = #filter_struct_ident;
 
-            fn with_fields<F, I>(&'a self, select: F) -> ::serde_partial::Partial<'a, Self>
+            const FIELD_COUNT: ::core::option::Option<usize> = #field_count;
+            const ALWAYS_FIELDS: &'static [&'static str] = &[#(#always_names),*];
+            const NEVER_FIELDS: &'static [&'static str] = &[#(#never_names),*];
+
+            fn with_fields<__F, __I>(&#partial_lifetime self, select: __F) -> ::serde_partial::Partial<#partial_lifetime, Self>
             where
-                F: ::core::ops::FnOnce(Self::Fields) -> I,
-                I: ::core::iter::IntoIterator<Item = ::serde_partial::Field<'a, Self>>,
+                __F: ::core::ops::FnOnce(Self::Fields) -> __I,
+                __I: ::core::iter::IntoIterator<Item = ::serde_partial::Field<#partial_lifetime, Self>>,
+            {
+                match Self::try_with_fields(self, select) {
+                    ::core::result::Result::Ok(partial) => partial,
+                    ::core::result::Result::Err(err) => ::core::panic!(
+                        "{} in `{}`",
+                        err,
+                        ::core::any::type_name::<Self>(),
+                    ),
+                }
+            }
+
+            fn try_with_fields<__F, __I>(&#partial_lifetime self, select: __F) -> ::core::result::Result<::serde_partial::Partial<#partial_lifetime, Self>, ::serde_partial::UnknownFieldError<#partial_lifetime>>
+            where
+                __F: ::core::ops::FnOnce(Self::Fields) -> __I,
+                __I: ::core::iter::IntoIterator<Item = ::serde_partial::Field<#partial_lifetime, Self>>,
             {
                 let fields = Self::Fields::FIELDS;
                 let mut filter = <Self::Filter as ::core::default::Default>::default();
 
                 for filtered in select(fields) {
-                    match filtered.name() {
+                    filter.select(filtered.name())?;
+                }
+
+                ::core::result::Result::Ok(::serde_partial::Partial {
+                    value: self,
+                    filter,
+                })
+            }
+
+            fn field_names() -> &'static [&'static str] {
+                Self::Fields::FIELD_NAMES
+            }
+        }
+    };
+
+    #[cfg(feature = "sql")]
+    let sql_impl = {
+        let sql_columns = field_idents
+            .iter()
+            .zip(&partial_attrs)
+            .map(|(i, a)| {
+                a.sql_column
+                    .as_ref()
+                    .map(|c| c.value())
+                    .unwrap_or_else(|| i.unraw().to_string())
+            })
+            .collect::<Vec<_>>();
+
+        quote::quote! {
+            impl #impl_generics ::serde_partial::sql::SqlColumns for #ty #where_clause {
+                const COLUMNS: &'static [::serde_partial::sql::FieldDescription] = &[
+                    #(
+                        ::serde_partial::sql::FieldDescription::new(#field_names, #sql_columns),
+                    )*
+                ];
+            }
+        }
+    };
+    #[cfg(not(feature = "sql"))]
+    let sql_impl = match partial_attrs.iter().find_map(|a| a.sql_column.as_ref()) {
+        Some(c) => Error::new_spanned(
+            c,
+            "`sql_column` requires the `sql` feature of serde-partial",
+        )
+        .to_compile_error(),
+        None => quote::quote! {},
+    };
+
+    let accessor_impl = if container_attrs.generate_accessor_fn {
+        let accessor_idents = field_idents
+            .iter()
+            .map(|i| quote::format_ident!("{}_field", i))
+            .collect::<Vec<_>>();
+        quote::quote! {
+            impl #impl_generics #ty #where_clause {
+                #(
+                    pub fn #accessor_idents() -> ::serde_partial::Field<'static, Self> {
+                        #fields_struct_ident::FIELDS.#field_idents
+                    }
+                )*
+            }
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let builder_impl = if container_attrs.builder {
+        let builder_ident = quote::format_ident!("{}Builder", filter_struct_ident);
+        quote::quote! {
+            #[derive(#filter_derives)]
+            #vis struct #builder_ident {
+                filter: #filter_struct_ident,
+            }
+
+            impl #builder_ident {
+                #(
+                    pub fn #field_idents(mut self, selected: bool) -> Self {
+                        self.filter.#field_idents = selected;
+                        self
+                    }
+                )*
+
+                pub fn build(self) -> #filter_struct_ident {
+                    self.filter
+                }
+            }
+
+            impl #filter_struct_ident {
+                pub fn builder() -> #builder_ident {
+                    <#builder_ident as ::core::default::Default>::default()
+                }
+            }
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let serde_fields_impl = if container_attrs.serde_fields {
+        quote::quote! {
+            impl #impl_generics ::serde_partial::__private::serde::Serialize for #fields_struct_ident #ty_generics #where_clause {
+                fn serialize<__S>(&self, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+                where
+                    __S: ::serde_partial::__private::serde::Serializer,
+                {
+                    if serializer.is_human_readable() {
+                        let mut map = ::serde_partial::__private::serde::Serializer::serialize_map(
+                            serializer,
+                            ::core::option::Option::Some(#fields_len),
+                        )?;
                         #(
-                            #field_names => { filter.#field_idents = true }
+                            ::serde_partial::__private::serde::ser::SerializeMap::serialize_entry(&mut map, #rust_names, #field_names)?;
                         )*
-                        _ => panic!("unknown field"),
+                        ::serde_partial::__private::serde::ser::SerializeMap::end(map)
+                    } else {
+                        ::serde_partial::__private::serde::Serialize::serialize(Self::FIELD_NAMES, serializer)
                     }
                 }
+            }
 
-                ::serde_partial::Partial {
-                    value: self,
-                    filter,
+            impl<'de> ::serde_partial::__private::serde::Deserialize<'de> for #filter_struct_ident {
+                fn deserialize<__D>(deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+                where
+                    __D: ::serde_partial::__private::serde::Deserializer<'de>,
+                {
+                    ::serde_partial::filter::deserialize_selection(deserializer)
+                }
+            }
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let codec_impl = if container_attrs.codec {
+        let indices = 0..fields_len;
+        quote::quote! {
+            impl ::serde_partial::codec::FieldCodec for #filter_struct_ident {
+                fn encode(&self) -> ::serde_partial::__private::Vec<u8> {
+                    ::serde_partial::codec::encode_bits(&[#(self.#field_idents),*])
+                }
+
+                fn decode(bytes: &[u8]) -> ::core::result::Result<Self, ::serde_partial::codec::DecodeError> {
+                    let mut bits = [false; #fields_len];
+                    ::serde_partial::codec::decode_bits(bytes, &mut bits)?;
+
+                    let mut filter = <Self as ::core::default::Default>::default();
+                    #(
+                        filter.#field_idents = bits[#indices];
+                    )*
+                    ::core::result::Result::Ok(filter)
+                }
+            }
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    // the fields of enums depend on the variant
+    #[cfg(feature = "erased-serde")]
+    let erased_impl = if is_enum {
+        quote::quote! {}
+    } else {
+        let mut erased_generics = partial_generics.clone();
+        {
+            let predicates = &mut erased_generics.make_where_clause().predicates;
+            for f in &fields {
+                let fty = f.ty;
+                predicates
+                    .push(syn::parse_quote!(#fty: ::serde_partial::__private::serde::Serialize));
+            }
+        }
+        let (erased_impl_generics, _, erased_where_clause) = erased_generics.split_for_impl();
+        let entries = fields.iter().map(|f| {
+            let ident = f.original.ident.as_ref().unwrap();
+            let entry = quote::quote! {
+                ::core::option::Option::Some((
+                    #fields_struct_ident::FIELDS.#ident,
+                    &self.#ident as &#partial_lifetime dyn ::serde_partial::__private::erased_serde::Serialize,
+                ))
+            };
+            match f.attrs.skip_serializing_if() {
+                Some(predicate) => quote::quote! {
+                    if #predicate(&self.#ident) {
+                        ::core::option::Option::None
+                    } else {
+                        #entry
+                    }
+                },
+                None => entry,
+            }
+        });
+        quote::quote! {
+            impl #erased_impl_generics ::serde_partial::entries::ErasedFields<#partial_lifetime> for #ty #erased_where_clause {
+                type Iter = ::core::iter::Flatten<::core::array::IntoIter<
+                    ::core::option::Option<(
+                        ::serde_partial::Field<'static, Self>,
+                        &#partial_lifetime dyn ::serde_partial::__private::erased_serde::Serialize,
+                    )>,
+                    #fields_len,
+                >>;
+
+                fn iter_fields(&#partial_lifetime self) -> Self::Iter {
+                    #[allow(deprecated)]
+                    ::core::iter::Iterator::flatten(::core::array::IntoIter::new([
+                        #(
+                            #entries,
+                        )*
+                    ]))
                 }
             }
         }
     };
+    #[cfg(not(feature = "erased-serde"))]
+    let erased_impl = quote::quote! {};
 
     let derive = quote::quote! {
         #[doc(hidden)]
-        #[allow(non_upper_case_globals, non_camel_case_types)]
+        #[allow(non_upper_case_globals, non_camel_case_types, non_snake_case)]
         const _: () = {
             #fields_struct
             #filter_struct
             #trait_impl
+            #sql_impl
+            #codec_impl
+            #accessor_impl
+            #serde_fields_impl
+            #builder_impl
+            #erased_impl
         };
     };
     derive.into()
 }
+
+/// Derives the implementation for a newtype struct, which forwards to the implementation of the wrapped type.
+fn newtype(
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+    field: &serde_derive_internals::ast::Field<'_>,
+) -> proc_macro2::TokenStream {
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let ty = &quote::quote!(#ident #ty_generics);
+    let inner = field.ty;
+
+    let partial_lifetime: syn::Lifetime = syn::parse_quote!('__a);
+    let mut partial_generics = partial_generics(generics, ty, &partial_lifetime);
+    partial_generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(#inner: ::serde_partial::SerializePartial<#partial_lifetime>));
+    let (partial_impl_generics, _, partial_where_clause) = partial_generics.split_for_impl();
+    let inner_partial =
+        quote::quote!(<#inner as ::serde_partial::SerializePartial<#partial_lifetime>>);
+
+    quote::quote! {
+        #[doc(hidden)]
+        const _: () = {
+            impl #partial_impl_generics ::serde_partial::SerializePartial<#partial_lifetime> for #ty #partial_where_clause {
+                type Fields = #inner_partial::Fields;
+                type Filter = ::serde_partial::filter::NewtypeFilter<Self, #inner, #inner_partial::Filter>;
+
+                const FIELD_COUNT: ::core::option::Option<usize> = #inner_partial::FIELD_COUNT;
+                const ALWAYS_FIELDS: &'static [&'static str] = #inner_partial::ALWAYS_FIELDS;
+                const NEVER_FIELDS: &'static [&'static str] = #inner_partial::NEVER_FIELDS;
+
+                fn with_fields<__F, __I>(&#partial_lifetime self, select: __F) -> ::serde_partial::Partial<#partial_lifetime, Self>
+                where
+                    __F: ::core::ops::FnOnce(Self::Fields) -> __I,
+                    __I: ::core::iter::IntoIterator<Item = ::serde_partial::Field<#partial_lifetime, Self>>,
+                {
+                    let inner = #inner_partial::with_fields(&self.0, |fields| {
+                        ::core::iter::Iterator::map(
+                            ::core::iter::IntoIterator::into_iter(select(fields)),
+                            ::serde_partial::Field::cast,
+                        )
+                    });
+
+                    ::serde_partial::Partial {
+                        value: self,
+                        filter: ::serde_partial::filter::NewtypeFilter::new(inner.filter),
+                    }
+                }
+
+                fn try_with_fields<__F, __I>(&#partial_lifetime self, select: __F) -> ::core::result::Result<::serde_partial::Partial<#partial_lifetime, Self>, ::serde_partial::UnknownFieldError<#partial_lifetime>>
+                where
+                    __F: ::core::ops::FnOnce(Self::Fields) -> __I,
+                    __I: ::core::iter::IntoIterator<Item = ::serde_partial::Field<#partial_lifetime, Self>>,
+                {
+                    let inner = #inner_partial::try_with_fields(&self.0, |fields| {
+                        ::core::iter::Iterator::map(
+                            ::core::iter::IntoIterator::into_iter(select(fields)),
+                            ::serde_partial::Field::cast,
+                        )
+                    })?;
+
+                    ::core::result::Result::Ok(::serde_partial::Partial {
+                        value: self,
+                        filter: ::serde_partial::filter::NewtypeFilter::new(inner.filter),
+                    })
+                }
+
+                fn field_names() -> &'static [&'static str] {
+                    #inner_partial::field_names()
+                }
+            }
+        };
+    }
+}
+
+/// Formats a type the way it is usually written, since `core::any::type_name` can't be used in constants.
+fn type_name(ty: &syn::Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let chars = tokens.chars().collect::<Vec<_>>();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut name = String::with_capacity(tokens.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = chars[i - 1];
+            let next = chars[i + 1];
+            let keep = (is_ident(prev) && is_ident(next))
+                || matches!(prev, ',' | ';' | '+')
+                || matches!(next, '+' | '-')
+                || name.ends_with("->");
+            if !keep {
+                continue;
+            }
+        }
+        name.push(c);
+    }
+    name
+}
+
+/// Generics of the `SerializePartial<'__a>` implementation, which require every parameter to outlive `'__a`.
+fn partial_generics(
+    generics: &syn::Generics,
+    ty: &proc_macro2::TokenStream,
+    partial_lifetime: &syn::Lifetime,
+) -> syn::Generics {
+    let mut partial_generics = generics.clone();
+    {
+        let predicates = &mut partial_generics.make_where_clause().predicates;
+        for param in &generics.params {
+            match param {
+                GenericParam::Type(t) => {
+                    let t = &t.ident;
+                    predicates.push(syn::parse_quote!(#t: #partial_lifetime));
+                }
+                GenericParam::Lifetime(l) => {
+                    let l = &l.lifetime;
+                    predicates.push(syn::parse_quote!(#l: #partial_lifetime));
+                }
+                GenericParam::Const(_) => {}
+            }
+        }
+        predicates.push(syn::parse_quote!(#ty: ::serde_partial::__private::serde::Serialize));
+    }
+    partial_generics
+        .params
+        .insert(0, syn::parse_quote!(#partial_lifetime));
+    partial_generics
+}
+
+/// Builds a struct whose only field stands for the tag of an internally tagged enum.
+fn tag_item(tag: &str) -> DeriveInput {
+    // the field is named after the tag when possible so it's easy to select
+    let ident = syn::parse_str::<syn::Ident>(tag)
+        .or_else(|_| syn::parse_str::<syn::Ident>(&format!("r#{}", tag)))
+        .unwrap_or_else(|_| quote::format_ident!("tag"));
+    syn::parse_quote! {
+        struct Tag {
+            #[serde(rename = #tag)]
+            #[serde_partial(always)]
+            #ident: &'static str,
+        }
+    }
+}
+
+/// Collects the fields of all the struct variants of an enum, which are selected by name regardless of the variant.
+fn enum_fields<'a>(
+    attrs: &serde_derive_internals::attr::Container,
+    variants: Vec<Variant<'a>>,
+    tag_field: Option<serde_derive_internals::ast::Field<'a>>,
+) -> Result<Vec<serde_derive_internals::ast::Field<'a>>, Error> {
+    if !matches!(attrs.tag(), TagType::None | TagType::Internal { .. }) {
+        return Err(Error::new(
+            Span::call_site().into(),
+            "SerializePartial only supports untagged and internally tagged enums",
+        ));
+    }
+
+    let mut fields: Vec<serde_derive_internals::ast::Field<'a>> = tag_field.into_iter().collect();
+    for variant in variants {
+        if variant.attrs.skip_serializing() {
+            continue;
+        }
+        match variant.style {
+            Style::Struct => {}
+            Style::Newtype => {
+                return Err(Error::new_spanned(
+                    variant.original,
+                    "SerializePartial doesn't support newtype variants",
+                ))
+            }
+            Style::Tuple | Style::Unit => continue,
+        }
+
+        for field in variant.fields {
+            let ident = field.original.ident.as_ref();
+            let name = field.attrs.name().serialize_name();
+            let same_ident =
+                |f: &serde_derive_internals::ast::Field<'_>| f.original.ident.as_ref() == ident;
+            let same_name = |f: &serde_derive_internals::ast::Field<'_>| {
+                f.attrs.name().serialize_name() == name
+            };
+
+            match fields.iter().find(|f| same_ident(f) || same_name(f)) {
+                Some(f) if same_ident(f) && same_name(f) => {}
+                Some(_) => {
+                    return Err(Error::new_spanned(
+                        field.original,
+                        "fields with the same identifier or name in different variants must be serialized with the same name",
+                    ))
+                }
+                None => fields.push(field),
+            }
+        }
+    }
+    Ok(fields)
+}
+
+/// Replaces the `#[serde(rename_all_fields = "...")]` container attribute, which serde_derive_internals doesn't know about,
+/// with `#[serde(rename_all = "...")]` on every variant which doesn't have its own rules, which is equivalent.
+fn expand_rename_all_fields(item: &mut DeriveInput) -> Result<(), Error> {
+    let variants = match &mut item.data {
+        syn::Data::Enum(data) => &mut data.variants,
+        _ => return Ok(()),
+    };
+
+    let mut rename_all_fields = None;
+    for attr in item.attrs.iter_mut().filter(|a| a.path.is_ident("serde")) {
+        let mut list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            _ => continue,
+        };
+        let len = list.nested.len();
+        list.nested = list
+            .nested
+            .into_iter()
+            .filter(|nested| match nested {
+                syn::NestedMeta::Meta(meta) if meta.path().is_ident("rename_all_fields") => {
+                    rename_all_fields = Some(meta.clone());
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+        if list.nested.len() != len {
+            *attr = syn::parse_quote!(#[#list]);
+        }
+    }
+
+    let rules = match rename_all_fields {
+        Some(syn::Meta::NameValue(nv)) => {
+            let lit = &nv.lit;
+            quote::quote!(rename_all = #lit)
+        }
+        Some(syn::Meta::List(list)) => {
+            let nested = &list.nested;
+            quote::quote!(rename_all(#nested))
+        }
+        Some(meta) => {
+            return Err(Error::new_spanned(
+                meta,
+                "expected `rename_all_fields = \"...\"`",
+            ))
+        }
+        None => return Ok(()),
+    };
+
+    for variant in variants {
+        let has_rules = variant
+            .attrs
+            .iter()
+            .filter(|a| a.path.is_ident("serde"))
+            .filter_map(|a| match a.parse_meta() {
+                Ok(syn::Meta::List(list)) => Some(list.nested),
+                _ => None,
+            })
+            .flatten()
+            .any(|nested| matches!(nested, syn::NestedMeta::Meta(meta) if meta.path().is_ident("rename_all")));
+        if !has_rules {
+            variant.attrs.push(syn::parse_quote!(#[serde(#rules)]));
+        }
+    }
+
+    Ok(())
+}
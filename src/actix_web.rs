@@ -0,0 +1,157 @@
+//! actix-web integration
+//!
+//! [`PartialFields`] is an extractor which parses a comma separated list of fields from the `fields` query parameter.
+//! Requests selecting unknown fields are rejected with a `400 Bad Request` response with a JSON body describing the error.
+//!
+//! ## Example
+//!
+//! ```
+//! use actix_web_crate::{web, App};
+//! use serde::Serialize;
+//! use serde_partial::{actix_web::PartialFields, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//!     age: u8,
+//!     email: &'static str,
+//! }
+//!
+//! const USER: User = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+//!
+//! async fn get_user(fields: PartialFields<User>) -> String {
+//!     serde_json::to_string(&fields.into_model(USER)).unwrap()
+//! }
+//!
+//! let app = App::new().route("/user", web::get().to(get_user));
+//!
+//! let fields = PartialFields::<User>::from_query("fields=name,age").unwrap();
+//! let json = serde_json::to_value(&fields.into_model(USER)).unwrap();
+//! assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+//!
+//! let err = PartialFields::<User>::from_query("fields=name,phone").unwrap_err();
+//! assert_eq!(err.message(), "unknown field `phone`, expected one of `name`, `age`, `email`");
+//! ```
+
+use core::{fmt, future::Ready};
+use std::{
+    collections::HashMap,
+    string::{String, ToString},
+};
+
+use actix_web_crate::{
+    dev::Payload,
+    http::{header::ContentType, StatusCode},
+    web::Query,
+    FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+
+use crate::{field_mask, filter::SelectFilter, model::Model, SerializePartial, UnknownFieldError};
+
+type Filter<T> = <T as SerializePartial<'static>>::Filter;
+
+/// Extractor for the fields selected by the `fields` query parameter.
+///
+/// All the fields are selected if the parameter is missing.
+pub struct PartialFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// The filter selecting the requested fields.
+    pub filter: Filter<T>,
+}
+
+/// Error returned when the requested fields can't be parsed.
+///
+/// This error responds with `400 Bad Request` and a JSON body of the form `{ "error": "..." }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialFieldsError {
+    message: String,
+}
+
+impl<T> PartialFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: SelectFilter,
+{
+    /// Parses the fields selected by the `fields` parameter of the query string.
+    pub fn from_query(query: &str) -> Result<Self, PartialFieldsError> {
+        let Query(mut params) =
+            Query::<HashMap<String, String>>::from_query(query).map_err(|err| {
+                PartialFieldsError {
+                    message: err.to_string(),
+                }
+            })?;
+        let filter = match params.remove("fields") {
+            Some(fields) => field_mask::select(&fields).map_err(PartialFieldsError::unknown)?,
+            None => field_mask::select_all::<T>(),
+        };
+        Ok(Self { filter })
+    }
+}
+
+impl<T> PartialFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// Returns a model which only serializes the requested fields of the provided value.
+    pub fn into_model(self, value: T) -> Model<T> {
+        Model::new(value, self.filter)
+    }
+}
+
+impl PartialFieldsError {
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn unknown(err: UnknownFieldError<'_>) -> Self {
+        Self {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl<T> FromRequest for PartialFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: SelectFilter,
+{
+    type Error = PartialFieldsError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        core::future::ready(Self::from_query(req.query_string()))
+    }
+}
+
+impl ResponseError for PartialFieldsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::BadRequest()
+            .content_type(ContentType::json())
+            .body(serde_json::json!({ "error": self.message }).to_string())
+    }
+}
+
+impl fmt::Display for PartialFieldsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PartialFieldsError {}
+
+impl<T> fmt::Debug for PartialFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PartialFields").field(&self.filter).finish()
+    }
+}
@@ -4,9 +4,12 @@ use alloc::collections::{
 };
 use core::iter::Map;
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
-use crate::{Field, Partial, SerializeFilter, SerializePartial};
+use crate::{
+    key::{KeyPartial, SerializeKeyPartial},
+    Field, Partial, SerializeFilter, SerializePartial,
+};
 
 impl<'a, K, V> SerializePartial<'a> for BTreeMap<K, V>
 where
@@ -23,7 +26,13 @@ where
         I: IntoIterator<Item = Field<'a, Self>>,
     {
         let fields: Self::Fields = self.keys().map(|k| Field::new(k.as_ref()));
-        let filter = select(fields).into_iter().collect();
+        let selected: Self::Filter = select(fields).into_iter().collect();
+        // fields which don't match any key would make the filtered length too large
+        let filter = self
+            .keys()
+            .map(|k| Field::new(k.as_ref()))
+            .filter(|f| selected.contains(f))
+            .collect();
         Partial {
             value: self,
             filter,
@@ -41,6 +50,39 @@ impl<'a, K, V> SerializeFilter<BTreeMap<K, V>> for BTreeSet<Field<'a, BTreeMap<K
     }
 }
 
+impl<'a, K, V> SerializeKeyPartial<'a> for BTreeMap<K, V>
+where
+    K: Ord + Serialize + 'a,
+    V: Serialize + 'a,
+{
+    type Key = K;
+    type Keys = Keys<'a, K, V>;
+    type KeyFilter = BTreeSet<K>;
+
+    fn with_key_fields<F, I>(&'a self, select: F) -> KeyPartial<'a, Self>
+    where
+        F: FnOnce(Self::Keys) -> I,
+        I: IntoIterator<Item = Self::Key>,
+    {
+        let filter = select(self.keys()).into_iter().collect();
+        KeyPartial {
+            value: self,
+            filter,
+        }
+    }
+
+    fn serialize_key_partial<S>(
+        &self,
+        filter: &Self::KeyFilter,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.iter().filter(|(k, _)| filter.contains(k)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Field, SerializePartial};
@@ -56,4 +98,32 @@ mod tests {
             serde_json::json!({ "a": "b" })
         )
     }
+
+    #[test]
+    fn b_tree_map_filtered_len() {
+        let map = BTreeMap::from([("a", "b"), ("c", "d")]);
+        let filtered = map.with_fields(|_| [Field::new("a"), Field::new("a"), Field::new("e")]);
+        assert_eq!(filtered.len(), Some(1));
+    }
+
+    #[test]
+    // the borrows are what's being tested
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    fn serialize_by_reference_and_value() {
+        fn to_value(value: impl serde::Serialize) -> serde_json::Value {
+            serde_json::to_value(value).unwrap()
+        }
+
+        let map = BTreeMap::from([("a", "b"), ("c", "d")]);
+        let filtered = map.with_fields(|_| [Field::new("c")]);
+        let expected = serde_json::json!({ "c": "d" });
+
+        assert_eq!(to_value(&filtered), expected);
+        assert_eq!(to_value(&&filtered), expected);
+        assert_eq!(
+            to_value(&mut map.with_fields(|_| [Field::new("c")])),
+            expected
+        );
+        assert_eq!(to_value(filtered), expected);
+    }
 }
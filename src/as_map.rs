@@ -0,0 +1,196 @@
+use core::fmt;
+
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+/// Serializer which serializes structs as maps and forwards everything else.
+pub(crate) struct StructAsMap<S> {
+    pub(crate) s: S,
+}
+
+pub(crate) struct StructAsMapSerializeStruct<M> {
+    sm: M,
+}
+
+impl<M> SerializeStruct for StructAsMapSerializeStruct<M>
+where
+    M: SerializeMap,
+{
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<TT>(&mut self, key: &'static str, value: &TT) -> Result<(), Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.sm.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.sm.end()
+    }
+    // skip_field not implemented because skipped fields simply don't appear in maps
+}
+
+impl<S> Serializer for StructAsMap<S>
+where
+    S: Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeStruct = StructAsMapSerializeStruct<S::SerializeMap>;
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let sm = self.s.serialize_map(Some(len))?;
+        Ok(StructAsMapSerializeStruct { sm })
+    }
+
+    type SerializeSeq = S::SerializeSeq;
+    type SerializeTuple = S::SerializeTuple;
+    type SerializeTupleStruct = S::SerializeTupleStruct;
+    type SerializeTupleVariant = S::SerializeTupleVariant;
+    type SerializeMap = S::SerializeMap;
+    type SerializeStructVariant = S::SerializeStructVariant;
+
+    fn is_human_readable(&self) -> bool {
+        self.s.is_human_readable()
+    }
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_bool(v)
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_i8(v)
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_i16(v)
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_i32(v)
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_i64(v)
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_i128(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_u8(v)
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_u16(v)
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_u32(v)
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_u64(v)
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_u128(v)
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_f32(v)
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_f64(v)
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_char(v)
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_str(v)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_bytes(v)
+    }
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_none()
+    }
+    fn serialize_some<TT>(self, value: &TT) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.s.serialize_some(value)
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_unit()
+    }
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_unit_struct(name)
+    }
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.s.serialize_unit_variant(name, index, variant)
+    }
+    fn serialize_newtype_struct<TT>(
+        self,
+        name: &'static str,
+        value: &TT,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.s.serialize_newtype_struct(name, value)
+    }
+    fn serialize_newtype_variant<TT>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &TT,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.s
+            .serialize_newtype_variant(name, index, variant, value)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.s.serialize_seq(len)
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.s.serialize_tuple(len)
+    }
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.s.serialize_tuple_struct(name, len)
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.s.serialize_tuple_variant(name, index, variant, len)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.s.serialize_map(len)
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.s.serialize_struct_variant(name, index, variant, len)
+    }
+    fn collect_str<TT>(self, value: &TT) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + fmt::Display,
+    {
+        self.s.collect_str(value)
+    }
+}
@@ -0,0 +1,49 @@
+//! Compile-time trait implementation assertions
+//!
+//! The functions in this module don't do anything at runtime but fail to compile if their type parameters don't implement the relevant traits.
+//! Coercing them to function pointers in a constant makes the check happen at compile time without having to call them,
+//! which is useful to guard against accidental removal of derives in test suites.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{assert_impls, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//! }
+//!
+//! const _: fn() = assert_impls::assert_serialize_partial::<User>;
+//! const _: fn() = assert_impls::assert_serialize_filter::<<User as SerializePartial>::Filter, User>;
+//! ```
+//!
+//! ```compile_fail
+//! use serde::Serialize;
+//! use serde_partial::assert_impls;
+//!
+//! #[derive(Serialize)]
+//! struct User {
+//!     name: &'static str,
+//! }
+//!
+//! const _: fn() = assert_impls::assert_serialize_partial::<User>;
+//! ```
+
+use crate::{SerializeFilter, SerializePartial};
+
+/// Fails to compile if `T` doesn't implement [`SerializePartial`].
+pub fn assert_serialize_partial<'a, T>()
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+}
+
+/// Fails to compile if `F` doesn't implement [`SerializeFilter<T>`].
+pub fn assert_serialize_filter<F, T>()
+where
+    F: ?Sized + SerializeFilter<T>,
+    T: ?Sized,
+{
+}
@@ -0,0 +1,240 @@
+//! axum integration
+//!
+//! [`FieldMask`] and [`ExcludeFields`] are extractors which parse a comma separated list of fields
+//! from the `fields` and `exclude_fields` query parameters respectively.
+//! Requests selecting unknown fields are rejected with a `400 Bad Request` response with a JSON body describing the error.
+//!
+//! ## Example
+//!
+//! ```
+//! use axum_crate::{http::Uri, routing::get, Router};
+//! use serde::Serialize;
+//! use serde_partial::{
+//!     axum::{ExcludeFields, FieldMask},
+//!     SerializePartial,
+//! };
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//!     age: u8,
+//!     email: &'static str,
+//! }
+//!
+//! const USER: User = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+//!
+//! async fn get_user(mask: FieldMask<User>) -> String {
+//!     serde_json::to_string(&mask.into_model(USER)).unwrap()
+//! }
+//!
+//! let app: Router = Router::new().route("/user", get(get_user));
+//!
+//! let uri: Uri = "/user?fields=name,age".parse().unwrap();
+//! let mask = FieldMask::<User>::from_uri(&uri).unwrap();
+//! let json = serde_json::to_value(&mask.into_model(USER)).unwrap();
+//! assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+//!
+//! let uri: Uri = "/user?exclude_fields=email".parse().unwrap();
+//! let exclude = ExcludeFields::<User>::from_uri(&uri).unwrap();
+//! let json = serde_json::to_value(&exclude.into_model(USER)).unwrap();
+//! assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+//!
+//! let uri: Uri = "/user?fields=name,phone".parse().unwrap();
+//! let err = FieldMask::<User>::from_uri(&uri).unwrap_err();
+//! assert_eq!(err.message(), "unknown field `phone`, expected one of `name`, `age`, `email`");
+//! ```
+
+use core::{fmt, future::Future};
+use std::{
+    collections::HashMap,
+    string::{String, ToString},
+};
+
+use axum_crate::{
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    field_mask,
+    filter::{InverseFilter, SelectFilter},
+    model::Model,
+    SerializePartial, UnknownFieldError,
+};
+
+type Filter<T> = <T as SerializePartial<'static>>::Filter;
+
+/// Extractor for the fields selected by the `fields` query parameter.
+///
+/// All the fields are selected if the parameter is missing.
+pub struct FieldMask<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// The filter selecting the requested fields.
+    pub filter: Filter<T>,
+}
+
+/// Extractor for the fields skipped by the `exclude_fields` query parameter.
+///
+/// No field is skipped if the parameter is missing.
+pub struct ExcludeFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// The filter skipping the requested fields.
+    pub filter: InverseFilter<'static, T, Filter<T>>,
+}
+
+/// Rejection returned when the requested fields can't be parsed.
+///
+/// This rejection responds with `400 Bad Request` and a JSON body of the form `{ "error": "..." }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMaskRejection {
+    message: String,
+}
+
+impl<T> FieldMask<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: SelectFilter,
+{
+    /// Parses the fields selected by the `fields` query parameter of the URI.
+    pub fn from_uri(uri: &Uri) -> Result<Self, FieldMaskRejection> {
+        let filter = match query_param(uri, "fields")? {
+            Some(fields) => field_mask::select(&fields).map_err(FieldMaskRejection::unknown)?,
+            None => field_mask::select_all::<T>(),
+        };
+        Ok(Self { filter })
+    }
+}
+
+impl<T> FieldMask<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// Returns a model which only serializes the requested fields of the provided value.
+    pub fn into_model(self, value: T) -> Model<T> {
+        Model::new(value, self.filter)
+    }
+}
+
+impl<T> ExcludeFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: SelectFilter,
+{
+    /// Parses the fields skipped by the `exclude_fields` query parameter of the URI.
+    pub fn from_uri(uri: &Uri) -> Result<Self, FieldMaskRejection> {
+        let filter = match query_param(uri, "exclude_fields")? {
+            Some(fields) => field_mask::select(&fields).map_err(FieldMaskRejection::unknown)?,
+            None => <Filter<T>>::default(),
+        };
+        Ok(Self {
+            filter: InverseFilter::new(filter),
+        })
+    }
+}
+
+impl<T> ExcludeFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+{
+    /// Returns a model which skips the requested fields of the provided value.
+    pub fn into_model(self, value: T) -> Model<T, InverseFilter<'static, T, Filter<T>>> {
+        Model::new(value, self.filter)
+    }
+}
+
+impl FieldMaskRejection {
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn unknown(err: UnknownFieldError<'_>) -> Self {
+        Self {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl<T, S> FromRequestParts<S> for FieldMask<T>
+where
+    T: SerializePartial<'static> + Send + Sync + 'static,
+    Filter<T>: SelectFilter + Send,
+    S: Send + Sync,
+{
+    type Rejection = FieldMaskRejection;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
+        core::future::ready(Self::from_uri(&parts.uri))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for ExcludeFields<T>
+where
+    T: SerializePartial<'static> + Send + Sync + 'static,
+    Filter<T>: SelectFilter + Send,
+    S: Send + Sync,
+{
+    type Rejection = FieldMaskRejection;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
+        core::future::ready(Self::from_uri(&parts.uri))
+    }
+}
+
+impl IntoResponse for FieldMaskRejection {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message }).to_string();
+        (
+            StatusCode::BAD_REQUEST,
+            [(header::CONTENT_TYPE, "application/json")],
+            body,
+        )
+            .into_response()
+    }
+}
+
+impl fmt::Display for FieldMaskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FieldMaskRejection {}
+
+impl<T> fmt::Debug for FieldMask<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FieldMask").field(&self.filter).finish()
+    }
+}
+impl<T> fmt::Debug for ExcludeFields<T>
+where
+    T: SerializePartial<'static> + 'static,
+    Filter<T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ExcludeFields").field(&self.filter).finish()
+    }
+}
+
+fn query_param(uri: &Uri, name: &str) -> Result<Option<String>, FieldMaskRejection> {
+    let Query(mut params) =
+        Query::<HashMap<String, String>>::try_from_uri(uri).map_err(|err| FieldMaskRejection {
+            message: err.body_text(),
+        })?;
+    Ok(params.remove(name))
+}
@@ -0,0 +1,143 @@
+//! Compact binary encoding of field selections
+//!
+//! Selections are encoded as a bitmask of the selected fields in declaration order,
+//! stored 7 bits per byte with the high bit of each byte set when more bytes follow.
+//! Trailing unselected fields are omitted, so selecting only the first few fields of a large struct produces a single byte.
+//!
+//! The derive macro implements [`FieldCodec`] for the generated [`Filter`][crate::SerializePartial::Filter]
+//! when the `#[serde_partial(codec)]` container attribute is specified. Nested filters aren't encoded.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{codec::FieldCodec, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! #[serde_partial(codec)]
+//! struct User {
+//!     name: &'static str,
+//!     age: u8,
+//!     email: &'static str,
+//! }
+//!
+//! let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+//! let filter = user.with_fields(|u| [u.name, u.email]).filter;
+//!
+//! let bytes = filter.encode();
+//! assert_eq!(bytes, [0b101]);
+//! assert_eq!(FieldCodec::decode(&bytes), Ok(filter));
+//!
+//! type UserFilter = <User as SerializePartial<'static>>::Filter;
+//! assert!(UserFilter::decode(&[0b1000]).is_err());
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Trait implemented by filters which can be encoded to and decoded from bytes.
+pub trait FieldCodec: Sized {
+    /// Encodes the selected fields.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes the selected fields.
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// Error returned when decoding a field selection fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The input ended while more bytes were expected.
+    Truncated,
+    /// The input contains bytes after the end of the bitmask.
+    TrailingBytes,
+    /// The bitmask selects a field with the specified index, which doesn't exist.
+    UnknownField(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => f.write_str("truncated field selection"),
+            DecodeError::TrailingBytes => f.write_str("trailing bytes after field selection"),
+            DecodeError::UnknownField(index) => write!(f, "unknown field at index {}", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Encodes a bitmask where each element indicates whether the field at the same index is selected.
+pub fn encode_bits(bits: &[bool]) -> Vec<u8> {
+    let len = bits.iter().rposition(|&b| b).map_or(0, |i| i + 1);
+    let mut bytes = Vec::with_capacity(len / 7 + 1);
+    for (i, chunk) in bits[..len].chunks(7).enumerate() {
+        let mut byte = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |byte, (j, &b)| byte | (u8::from(b) << j));
+        if (i + 1) * 7 < len {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+    }
+    if bytes.is_empty() {
+        bytes.push(0);
+    }
+    bytes
+}
+
+/// Decodes a bitmask encoded with [`encode_bits`] into `bits`, which should have one element per field.
+pub fn decode_bits(bytes: &[u8], bits: &mut [bool]) -> Result<(), DecodeError> {
+    let mut bytes = bytes.iter().enumerate();
+    loop {
+        let (i, &byte) = bytes.next().ok_or(DecodeError::Truncated)?;
+        for j in 0..7 {
+            if byte & (1 << j) != 0 {
+                let index = i * 7 + j;
+                match bits.get_mut(index) {
+                    Some(bit) => *bit = true,
+                    None => return Err(DecodeError::UnknownField(index)),
+                }
+            }
+        }
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    match bytes.next() {
+        Some(_) => Err(DecodeError::TrailingBytes),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_bits, encode_bits, DecodeError};
+
+    #[test]
+    fn roundtrip() {
+        let mut bits = [false; 20];
+        bits[0] = true;
+        bits[9] = true;
+        bits[19] = true;
+
+        let bytes = encode_bits(&bits);
+        assert_eq!(bytes, [0b1000_0001, 0b1000_0100, 0b0010_0000]);
+
+        let mut decoded = [false; 20];
+        decode_bits(&bytes, &mut decoded).unwrap();
+        assert_eq!(decoded, bits);
+
+        assert_eq!(encode_bits(&[false; 3]), [0]);
+        assert_eq!(
+            decode_bits(&[0x80], &mut decoded),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(
+            decode_bits(&[0, 0], &mut decoded),
+            Err(DecodeError::TrailingBytes)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Elasticsearch integration
+//!
+//! Source filters make it possible to only fetch the fields which are going to be serialized,
+//! using the same filter for both the query and the serialization.
+
+use alloc::{string::String, vec::Vec};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::{Field, SerializeFilter, SerializePartial};
+
+/// Elasticsearch `_source` filter listing the included and excluded fields.
+///
+/// This type serializes to the object expected by the `_source` field of a search query.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct EsSourceFilter {
+    /// Names of the included fields.
+    pub includes: Vec<String>,
+    /// Names of the excluded fields.
+    pub excludes: Vec<String>,
+}
+
+impl Serialize for EsSourceFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut ss = serializer.serialize_struct("EsSourceFilter", 2)?;
+        ss.serialize_field("includes", &self.includes)?;
+        ss.serialize_field("excludes", &self.excludes)?;
+        ss.end()
+    }
+}
+
+/// Returns an Elasticsearch `_source` filter including the fields selected by the filter and excluding the other ones.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{elasticsearch::filter_to_es_source_filter, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// const USER: User = User {
+///     full_name: "John Doe",
+///     age: 42,
+///     email: "john.doe@example.com",
+/// };
+///
+/// let filtered = USER.with_fields(|u| [u.full_name, u.age]);
+/// let source = filter_to_es_source_filter::<User, _>(&filtered.filter);
+/// assert_eq!(source.includes, ["fullName", "age"]);
+/// assert_eq!(source.excludes, ["email"]);
+///
+/// let query = serde_json::json!({ "_source": source });
+/// assert_eq!(
+///     query,
+///     serde_json::json!({ "_source": { "includes": ["fullName", "age"], "excludes": ["email"] } })
+/// );
+/// ```
+pub fn filter_to_es_source_filter<'a, T, F>(filter: &F) -> EsSourceFilter
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    let mut source = EsSourceFilter::default();
+    for name in T::field_names() {
+        if filter.skip(Field::new(name)) {
+            source.excludes.push((*name).into());
+        } else {
+            source.includes.push((*name).into());
+        }
+    }
+    source
+}
@@ -0,0 +1,680 @@
+//! Type erased access to the serialized fields of partial values
+//!
+//! Instead of driving a serializer, [`Partial::entries`] captures the retained fields as
+//! `(name, value)` pairs whose values implement [`erased_serde::Serialize`][erased_serde_crate::Serialize].
+//! This makes it possible to feed partial values into sinks which don't implement [`Serializer`], such as loggers.
+//! Captured entries are also used to serialize the retained fields in a custom order with [`Partial::ordered`],
+//! and to merge the fields of several values with [`CompositePartial`].
+//! Types implementing [`ErasedFields`] can also be iterated without capturing anything.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::SerializePartial;
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//!     age: u8,
+//!     email: &'static str,
+//! }
+//!
+//! let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+//! let entries = user.without_fields(|u| [u.email]).entries().unwrap();
+//!
+//! let mut names = Vec::new();
+//! for (name, value) in &entries {
+//!     let json = serde_json::to_string(value).unwrap();
+//!     names.push(format!("{}={}", name, json));
+//! }
+//! assert_eq!(names, ["name=\"John Doe\"", "age=42"]);
+//! ```
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use erased_serde_crate::Serialize as ErasedSerialize;
+use serde::ser::{Error as _, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+use serde_value::Value;
+
+use crate::{Field, Partial, SerializeFilter, SerializePartial};
+
+/// Error returned when capturing the entries of a partial value fails.
+pub use serde_value::SerializerError as EntriesError;
+
+/// The retained fields of a partial value captured as `(name, value)` pairs.
+///
+/// Fields are stored in the order they were serialized in.
+/// Values are captured as owned trees, which means nested maps don't preserve the order of their entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entries {
+    entries: Vec<(String, Value)>,
+}
+
+impl Entries {
+    /// Returns an iterator over the captured fields.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Returns the number of captured fields.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no fields were captured.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'e> IntoIterator for &'e Entries {
+    type Item = (&'e str, &'e dyn ErasedSerialize);
+    type IntoIter = Iter<'e>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the captured fields of [`Entries`].
+#[derive(Debug, Clone)]
+pub struct Iter<'e> {
+    inner: core::slice::Iter<'e, (String, Value)>,
+}
+
+impl<'e> Iterator for Iter<'e> {
+    type Item = (&'e str, &'e dyn ErasedSerialize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(name, value)| (name.as_str(), value as &dyn ErasedSerialize))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a, T, F> Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    /// Captures the retained fields as `(name, value)` pairs.
+    ///
+    /// Fails if the value doesn't serialize to a struct or a map with keys serializing to strings or primitives.
+    pub fn entries(&self) -> Result<Entries, EntriesError> {
+        self.serialize(EntriesSerializer)
+            .map(|entries| Entries { entries })
+    }
+
+    /// Returns a value which serializes the retained fields in the provided order.
+    ///
+    /// See [`OrderedPartial`].
+    pub fn ordered<I>(&self, order: I) -> OrderedPartial<'_, 'a, T, F>
+    where
+        I: IntoIterator<Item = Field<'a, T>>,
+    {
+        OrderedPartial {
+            partial: self,
+            order: order.into_iter().collect(),
+        }
+    }
+}
+
+/// A [`Partial`] which serializes its retained fields in a specific order.
+///
+/// Serde serializes the fields of a struct in the order they are declared in,
+/// so the retained fields are first captured like with [`Partial::entries`] and then serialized as a map in the requested order.
+/// Retained fields missing from the order are serialized afterwards in their original order,
+/// and fields in the order which aren't retained are ignored.
+///
+/// Every serialization allocates the captured entries and copies the field values into owned trees,
+/// which is significantly slower than serializing the partial value directly.
+/// It should only be used when consumers rely on the order of the entries.
+///
+/// Returned by [`Partial::ordered`].
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let partial = user.without_fields(|u| [u.age]);
+///
+/// let fields = <User as SerializePartial>::Fields::FIELDS;
+/// let json = serde_json::to_string(&partial.ordered([fields.email, fields.age])).unwrap();
+/// assert_eq!(json, r#"{"email":"john.doe@example.com","name":"John Doe"}"#);
+/// ```
+pub struct OrderedPartial<'p, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    partial: &'p Partial<'a, T, F>,
+    order: Vec<Field<'a, T>>,
+}
+
+impl<'a, T, F> Serialize for OrderedPartial<'_, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut entries = self
+            .partial
+            .serialize(EntriesSerializer)
+            .map_err(S::Error::custom)?
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for field in &self.order {
+            let entry = entries
+                .iter_mut()
+                .find(|e| matches!(e, Some((name, _)) if field.is(name)));
+            if let Some((name, value)) = entry.and_then(Option::take) {
+                map.serialize_entry(&name, &value)?;
+            }
+        }
+        for (name, value) in entries.into_iter().flatten() {
+            map.serialize_entry(&name, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'a, T, F> Clone for OrderedPartial<'_, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            partial: self.partial,
+            order: self.order.clone(),
+        }
+    }
+}
+impl<'a, T, F> fmt::Debug for OrderedPartial<'_, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderedPartial")
+            .field("order", &self.order)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Trait implemented by types whose fields can be iterated as [`Field`]s paired with type erased references to their values.
+///
+/// When the `erased-serde` feature is enabled, the derive macro implements this trait for structs.
+/// Fields skipped by `#[serde(skip_serializing_if = "...")]` aren't yielded,
+/// but fields using `#[serde(serialize_with = "...")]` yield their value as is.
+///
+/// Unlike [`Partial::entries`], iterating doesn't serialize nor copy anything.
+/// [`Partial`] implements [`IntoIterator`] for such types, yielding the fields selected by its filter.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     email: Option<&'static str>,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: None };
+///
+/// let mut log = Vec::new();
+/// for (field, value) in user.with_fields(|u| [u.name, u.email]) {
+///     let json = serde_json::to_string(value).unwrap();
+///     log.push(format!("{}={}", field.name(), json));
+/// }
+/// assert_eq!(log, ["name=\"John Doe\""]);
+/// ```
+pub trait ErasedFields<'a>: SerializePartial<'a> {
+    /// Iterator over the fields and references to their values.
+    type Iter: Iterator<Item = (Field<'static, Self>, &'a dyn ErasedSerialize)>;
+
+    /// Returns an iterator over all the serialized fields and references to their values.
+    fn iter_fields(&'a self) -> Self::Iter;
+}
+
+/// Iterator over the fields selected by a filter and references to their values.
+///
+/// Returned by the [`IntoIterator`] implementations of [`Partial`], see [`ErasedFields`].
+pub struct FieldIterator<'a, T, F>
+where
+    T: ?Sized + ErasedFields<'a>,
+{
+    iter: T::Iter,
+    filter: F,
+}
+
+impl<'a, T, F> fmt::Debug for FieldIterator<'a, T, F>
+where
+    T: ?Sized + ErasedFields<'a>,
+    F: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldIterator")
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T, F> Iterator for FieldIterator<'a, T, F>
+where
+    T: ?Sized + ErasedFields<'a>,
+    F: SerializeFilter<T>,
+{
+    type Item = (Field<'static, T>, &'a dyn ErasedSerialize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let filter = &self.filter;
+        self.iter.find(|(field, _)| !filter.skip(*field))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a, T, F> IntoIterator for Partial<'a, T, F>
+where
+    T: ?Sized + ErasedFields<'a>,
+    F: SerializeFilter<T>,
+{
+    type Item = (Field<'static, T>, &'a dyn ErasedSerialize);
+    type IntoIter = FieldIterator<'a, T, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FieldIterator {
+            iter: self.value.iter_fields(),
+            filter: self.filter,
+        }
+    }
+}
+
+impl<'p, 'a, T, F> IntoIterator for &'p Partial<'a, T, F>
+where
+    T: ?Sized + ErasedFields<'a>,
+    F: SerializeFilter<T>,
+{
+    type Item = (Field<'static, T>, &'a dyn ErasedSerialize);
+    type IntoIter = FieldIterator<'a, T, &'p F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FieldIterator {
+            iter: self.value.iter_fields(),
+            filter: &self.filter,
+        }
+    }
+}
+
+/// A value which serializes the entries of several partial values as a single map.
+///
+/// Each part is captured like with [`Partial::entries`] when serializing, and its entries are merged into the map.
+/// When several parts serialize an entry with the same name, the value of the last part wins
+/// and the entry keeps the position of its first occurrence.
+/// Parts can be any value serializing to a struct or a map, not only [`Partial`]s.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{entries::CompositePartial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     id: u32,
+///     name: &'static str,
+///     email: &'static str,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Organization {
+///     id: u32,
+///     plan: &'static str,
+/// }
+///
+/// let user = User { id: 1, name: "John Doe", email: "john.doe@example.com" };
+/// let org = Organization { id: 2, plan: "free" };
+///
+/// let composite = CompositePartial::new()
+///     .add(user.with_fields(|u| [u.id, u.name]))
+///     .add(org.with_fields(|o| [o.id, o.plan]));
+///
+/// let json = serde_json::to_value(&composite).unwrap();
+/// assert_eq!(json, serde_json::json!({ "id": 2, "name": "John Doe", "plan": "free" }));
+/// ```
+#[derive(Default)]
+pub struct CompositePartial<'a> {
+    parts: Vec<Box<dyn ErasedSerialize + 'a>>,
+}
+
+impl<'a> CompositePartial<'a> {
+    /// Creates a composite value without any part.
+    pub fn new() -> Self {
+        Self { parts: Vec::new() }
+    }
+
+    /// Adds a part whose entries are merged after the ones of the previous parts.
+    // chaining reads better than implementing `Add`, which would require `+` between unrelated types
+    #[allow(clippy::should_implement_trait)]
+    #[must_use]
+    pub fn add<P>(mut self, part: P) -> Self
+    where
+        P: Serialize + 'a,
+    {
+        self.push(part);
+        self
+    }
+
+    /// Adds a part whose entries are merged after the ones of the previous parts.
+    pub fn push<P>(&mut self, part: P)
+    where
+        P: Serialize + 'a,
+    {
+        self.parts.push(Box::new(part));
+    }
+
+    /// Returns the number of parts.
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns `true` if there are no parts.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
+
+impl Serialize for CompositePartial<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut entries: Vec<(String, Value)> = Vec::new();
+        for part in &self.parts {
+            let part = (**part)
+                .serialize(EntriesSerializer)
+                .map_err(S::Error::custom)?;
+            for (name, value) in part {
+                match entries.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, v)) => *v = value,
+                    None => entries.push((name, value)),
+                }
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (name, value) in &entries {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl fmt::Debug for CompositePartial<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompositePartial")
+            .field("parts", &self.parts.len())
+            .finish()
+    }
+}
+
+static ENTRIES_ERR: &str = "entries can only be captured from structs and maps";
+
+struct EntriesSerializer;
+
+struct EntriesCollector {
+    entries: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+fn key_to_string(key: Value) -> Result<String, EntriesError> {
+    match key {
+        Value::String(s) => Ok(s),
+        Value::Char(c) => Ok(c.to_string()),
+        Value::Bool(v) => Ok(v.to_string()),
+        Value::U8(v) => Ok(v.to_string()),
+        Value::U16(v) => Ok(v.to_string()),
+        Value::U32(v) => Ok(v.to_string()),
+        Value::U64(v) => Ok(v.to_string()),
+        Value::I8(v) => Ok(v.to_string()),
+        Value::I16(v) => Ok(v.to_string()),
+        Value::I32(v) => Ok(v.to_string()),
+        Value::I64(v) => Ok(v.to_string()),
+        Value::Newtype(v) => key_to_string(*v),
+        _ => Err(EntriesError::custom(
+            "key should serialize to a string or a primitive",
+        )),
+    }
+}
+
+impl SerializeStruct for EntriesCollector {
+    type Ok = Vec<(String, Value)>;
+    type Error = EntriesError;
+
+    fn serialize_field<V>(&mut self, key: &'static str, value: &V) -> Result<(), Self::Error>
+    where
+        V: ?Sized + Serialize,
+    {
+        let value = serde_value::to_value(value)?;
+        self.entries.push((key.into(), value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.entries)
+    }
+}
+
+impl SerializeMap for EntriesCollector {
+    type Ok = Vec<(String, Value)>;
+    type Error = EntriesError;
+
+    fn serialize_key<K>(&mut self, key: &K) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+    {
+        self.key = Some(key_to_string(serde_value::to_value(key)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<V>(&mut self, value: &V) -> Result<(), Self::Error>
+    where
+        V: ?Sized + Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| EntriesError::custom("value serialized before its key"))?;
+        let value = serde_value::to_value(value)?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.entries)
+    }
+}
+
+impl Serializer for EntriesSerializer {
+    type Ok = Vec<(String, Value)>;
+    type Error = EntriesError;
+
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = EntriesCollector;
+    type SerializeStruct = EntriesCollector;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(EntriesCollector {
+            entries: Vec::with_capacity(len),
+            key: None,
+        })
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(EntriesCollector {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+    fn serialize_newtype_struct<TT>(
+        self,
+        _name: &'static str,
+        value: &TT,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_some<TT>(self, _value: &TT) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_newtype_variant<TT>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &TT,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Self::Error::custom(ENTRIES_ERR))
+    }
+}
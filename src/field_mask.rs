@@ -0,0 +1,29 @@
+//! Field mask parsing shared by the web framework integrations
+
+use crate::{filter::SelectFilter, SerializePartial, UnknownFieldError};
+
+/// Selects the fields of a comma separated list, ignoring whitespace and empty names.
+pub(crate) fn select<F>(fields: &str) -> Result<F, UnknownFieldError<'_>>
+where
+    F: SelectFilter,
+{
+    let mut filter = F::default();
+    for name in fields.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        filter.select(name)?;
+    }
+    Ok(filter)
+}
+
+/// Selects all the fields of the type.
+pub(crate) fn select_all<T>() -> <T as SerializePartial<'static>>::Filter
+where
+    T: SerializePartial<'static>,
+    <T as SerializePartial<'static>>::Filter: SelectFilter,
+{
+    let mut filter = <T as SerializePartial<'static>>::Filter::default();
+    for name in T::field_names() {
+        // the names come from the type itself so they can't be unknown
+        let _ = filter.select(name);
+    }
+    filter
+}
@@ -1,19 +1,2316 @@
 //! Field filtering
 
-use core::{fmt, marker::PhantomData};
+use core::{
+    cell::{Cell, RefCell},
+    fmt, hash,
+    marker::PhantomData,
+};
 
-use crate::{Field, SerializePartial};
+use serde::de;
+
+use crate::{Field, Partial, SerializePartial, UnknownFieldError};
 
 /// Trait implemented by types which can be used to filter the serializable fields of another type.
 pub trait SerializeFilter<T: ?Sized> {
     /// Returns whether the specified field should be skipped.
     fn skip(&self, field: Field<'_, T>) -> bool;
 
-    /// Returns the number of fields which will be serialized given the total field count.
-    fn filtered_len(&self, len: Option<usize>) -> Option<usize>;
+    /// Returns the number of fields which will be serialized given the total field count.
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize>;
+
+    /// Returns the filter to apply to the value of the specified field, if any.
+    ///
+    /// This makes it possible to only serialize some of the fields of nested values.
+    fn nested(&self, _field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        None
+    }
+
+    /// Same as [`skip`][SerializeFilter::skip] but also receives whether the serializer is human readable.
+    ///
+    /// This is what the serializer calls, and the default implementation forwards to [`skip`][SerializeFilter::skip].
+    /// See [`HumanReadableFilter`].
+    fn skip_hr(&self, field: Field<'_, T>, _human_readable: bool) -> bool {
+        self.skip(field)
+    }
+
+    /// Same as [`filtered_len`][SerializeFilter::filtered_len] but also receives whether the serializer is human readable.
+    ///
+    /// This is what the serializer calls, and the default implementation forwards to [`filtered_len`][SerializeFilter::filtered_len].
+    fn filtered_len_hr(&self, len: Option<usize>, _human_readable: bool) -> Option<usize> {
+        self.filtered_len(len)
+    }
+
+    /// Same as [`nested`][SerializeFilter::nested] but also receives whether the serializer is human readable.
+    ///
+    /// This is what the serializer calls, and the default implementation forwards to [`nested`][SerializeFilter::nested].
+    fn nested_hr(&self, field: Field<'_, T>, _human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.nested(field)
+    }
+
+    /// Same as [`skip_hr`][SerializeFilter::skip_hr] but also receives the position of the field in its struct.
+    ///
+    /// The position counts every field serde serializes or skips, in declaration order.
+    /// It is only a hint since serializers can be driven in other ways, so implementations must check it refers to the same field before relying on it.
+    /// The filters generated by the derive macro use it to look up fields by index instead of comparing their names,
+    /// and the default implementation forwards to [`skip_hr`][SerializeFilter::skip_hr].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_partial::{Field, SerializeFilter, SerializePartial};
+    ///
+    /// #[derive(Serialize, SerializePartial)]
+    /// struct User {
+    ///     #[serde_partial(never)]
+    ///     password: &'static str,
+    ///     #[serde(skip_serializing_if = "Option::is_none")]
+    ///     name: Option<&'static str>,
+    ///     age: u8,
+    /// }
+    ///
+    /// let user = User { password: "hunter2", name: None, age: 42 };
+    /// let filter = user.with_fields(|u| [u.age]).filter;
+    ///
+    /// assert!(filter.skip_at(Field::new("password"), 0, true));
+    /// assert!(filter.skip_at(Field::new("name"), 1, true));
+    /// assert!(!filter.skip_at(Field::new("age"), 2, true));
+    /// // a position which doesn't match the field falls back to comparing names
+    /// assert!(!filter.skip_at(Field::new("age"), 0, true));
+    ///
+    /// let json = serde_json::to_value(user.with_fields(|u| [u.age])).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    /// ```
+    fn skip_at(&self, field: Field<'_, T>, _position: usize, human_readable: bool) -> bool {
+        self.skip_hr(field, human_readable)
+    }
+
+    /// Returns whether [`filtered_len_hr`][SerializeFilter::filtered_len_hr] is exactly the number of fields the filter selects, when known.
+    ///
+    /// When it is, the remaining fields of a struct are skipped without consulting the filter once all the selected ones have been serialized.
+    /// The default implementation returns `false`, since an underestimated length would then hide selected fields.
+    /// The filters generated by the derive macro return `true`.
+    fn exact_len(&self) -> bool {
+        false
+    }
+}
+
+impl<T, F> SerializeFilter<T> for &F
+where
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        (**self).skip(field)
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        (**self).filtered_len(len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        (**self).nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        (**self).skip_hr(field, human_readable)
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        (**self).filtered_len_hr(len, human_readable)
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        (**self).nested_hr(field, human_readable)
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        (**self).skip_at(field, position, human_readable)
+    }
+
+    fn exact_len(&self) -> bool {
+        (**self).exact_len()
+    }
+}
+
+/// Trait implemented by filters whose fields can be selected by name.
+///
+/// When using the derive macro, the generated [`Filter`][SerializePartial::Filter] implements this trait
+/// by forwarding to its inherent `select` method, which also accepts dotted paths to nested fields.
+pub trait SelectFilter: Default {
+    /// Selects the field with the specified name.
+    fn select<'a>(&mut self, name: &'a str) -> Result<(), UnknownFieldError<'a>>;
+}
+
+/// Deserializes a filter from a sequence of field names, selecting each of them.
+///
+/// Fails if a name isn't known to the filter.
+/// When using the derive macro with the `#[serde_partial(serde_fields)]` container attribute,
+/// the generated [`Filter`][SerializePartial::Filter] implements [`Deserialize`][serde::Deserialize] using this function.
+///
+/// ## Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_partial::{filter::deserialize_selection, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+/// }
+///
+/// type UserFilter = <User as SerializePartial<'static>>::Filter;
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(r#"["fullName"]"#);
+/// let filter: UserFilter = deserialize_selection(&mut deserializer).unwrap();
+///
+/// let user = User { full_name: "John Doe", age: 42 };
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe" }));
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(r#"["email"]"#);
+/// assert!(deserialize_selection::<_, UserFilter>(&mut deserializer).is_err());
+/// ```
+pub fn deserialize_selection<'de, D, F>(deserializer: D) -> Result<F, D::Error>
+where
+    D: de::Deserializer<'de>,
+    F: SelectFilter,
+{
+    struct SelectionVisitor<F>(PhantomData<F>);
+
+    impl<'de, F: SelectFilter> de::Visitor<'de> for SelectionVisitor<F> {
+        type Value = F;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a sequence of field names")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut filter = F::default();
+            while seq.next_element_seed(SelectSeed(&mut filter))?.is_some() {}
+            Ok(filter)
+        }
+    }
+
+    struct SelectSeed<'f, F>(&'f mut F);
+
+    impl<'de, F: SelectFilter> de::DeserializeSeed<'de> for SelectSeed<'_, F> {
+        type Value = ();
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_str(self)
+        }
+    }
+
+    impl<'de, F: SelectFilter> de::Visitor<'de> for SelectSeed<'_, F> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a field name")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            self.0.select(v).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_seq(SelectionVisitor(PhantomData))
+}
+
+/// Object safe version of [`SerializeFilter`] which operates on field names.
+///
+/// This is used to filter nested values, whose type isn't known to the parent filter.
+pub trait ErasedFilter {
+    /// Returns whether the field with the specified name should be skipped.
+    fn skip(&self, name: &str) -> bool;
+
+    /// Returns the number of fields which will be serialized given the total field count.
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize>;
+
+    /// Returns the filter to apply to the value of the field with the specified name, if any.
+    fn nested(&self, name: &str) -> Option<&dyn ErasedFilter>;
+}
+
+impl<T: ?Sized> SerializeFilter<T> for dyn ErasedFilter + '_ {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        ErasedFilter::skip(self, field.name())
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        ErasedFilter::filtered_len(self, len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        ErasedFilter::nested(self, field.name())
+    }
+}
+
+/// A filter for the value of a nested field which implements [`ErasedFilter`].
+///
+/// When using the derive macro, fields marked with `#[serde_partial(nested)]` store their filter in this type.
+pub struct NestedFilter<T: ?Sized, F> {
+    filter: F,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T, F> ErasedFilter for NestedFilter<T, F>
+where
+    T: ?Sized,
+    F: SerializeFilter<T>,
+{
+    fn skip(&self, name: &str) -> bool {
+        self.filter.skip(Field::new(name))
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.filter.filtered_len(len)
+    }
+
+    fn nested(&self, name: &str) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(Field::new(name))
+    }
+}
+
+impl<T: ?Sized, F> NestedFilter<T, F> {
+    /// Creates a nested filter wrapping the provided one.
+    pub const fn new(filter: F) -> Self {
+        Self {
+            filter,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the wrapped filter.
+    pub const fn get(&self) -> &F {
+        &self.filter
+    }
+
+    /// Returns a mutable reference to the wrapped filter.
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+}
+
+impl<T: ?Sized, F: Default> Default for NestedFilter<T, F> {
+    fn default() -> Self {
+        Self::new(F::default())
+    }
+}
+impl<T: ?Sized, F: Clone> Clone for NestedFilter<T, F> {
+    fn clone(&self) -> Self {
+        Self::new(self.filter.clone())
+    }
+}
+impl<T: ?Sized, F: Copy> Copy for NestedFilter<T, F> {}
+impl<T: ?Sized, F: fmt::Debug> fmt::Debug for NestedFilter<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NestedFilter").field(&self.filter).finish()
+    }
+}
+impl<T: ?Sized, F: PartialEq> PartialEq for NestedFilter<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+    }
+}
+impl<T: ?Sized, F: Eq> Eq for NestedFilter<T, F> {}
+impl<T: ?Sized, F: hash::Hash> hash::Hash for NestedFilter<T, F> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.filter.hash(state);
+    }
+}
+
+/// A filter for a newtype struct `T` which forwards to the filter of the wrapped type `U`.
+///
+/// When using the derive macro, this is the [`Filter`][SerializePartial::Filter] of newtype structs.
+pub struct NewtypeFilter<T: ?Sized, U: ?Sized, F> {
+    filter: F,
+    _ty: PhantomData<fn(&T, &U)>,
+}
+
+impl<T, U, F> SerializeFilter<T> for NewtypeFilter<T, U, F>
+where
+    T: ?Sized,
+    U: ?Sized,
+    F: SerializeFilter<U>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.filter.skip(field.cast())
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.filter.filtered_len(len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field.cast())
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.filter.skip_hr(field.cast(), human_readable)
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        self.filter.filtered_len_hr(len, human_readable)
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.filter.nested_hr(field.cast(), human_readable)
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        self.filter.skip_at(field.cast(), position, human_readable)
+    }
+
+    fn exact_len(&self) -> bool {
+        self.filter.exact_len()
+    }
+}
+
+impl<T, U, F> SelectFilter for NewtypeFilter<T, U, F>
+where
+    T: ?Sized,
+    U: ?Sized,
+    F: SelectFilter,
+{
+    fn select<'a>(&mut self, name: &'a str) -> Result<(), UnknownFieldError<'a>> {
+        self.filter.select(name)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, F> NewtypeFilter<T, U, F> {
+    /// Creates a newtype filter wrapping the filter of the inner type.
+    pub const fn new(filter: F) -> Self {
+        Self {
+            filter,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the wrapped filter.
+    pub const fn get(&self) -> &F {
+        &self.filter
+    }
+
+    /// Returns a mutable reference to the wrapped filter.
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.filter
+    }
+
+    /// Returns the wrapped filter.
+    pub fn into_inner(self) -> F {
+        self.filter
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, F: Default> Default for NewtypeFilter<T, U, F> {
+    fn default() -> Self {
+        Self::new(F::default())
+    }
+}
+impl<T: ?Sized, U: ?Sized, F: Clone> Clone for NewtypeFilter<T, U, F> {
+    fn clone(&self) -> Self {
+        Self::new(self.filter.clone())
+    }
+}
+impl<T: ?Sized, U: ?Sized, F: Copy> Copy for NewtypeFilter<T, U, F> {}
+impl<T: ?Sized, U: ?Sized, F: fmt::Debug> fmt::Debug for NewtypeFilter<T, U, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NewtypeFilter").field(&self.filter).finish()
+    }
+}
+impl<T: ?Sized, U: ?Sized, F: PartialEq> PartialEq for NewtypeFilter<T, U, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+    }
+}
+impl<T: ?Sized, U: ?Sized, F: Eq> Eq for NewtypeFilter<T, U, F> {}
+impl<T: ?Sized, U: ?Sized, F: hash::Hash> hash::Hash for NewtypeFilter<T, U, F> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.filter.hash(state);
+    }
+}
+
+/// A [`SerializeFilter`] which serializes all the fields.
+///
+/// This is useful as a default in code generic over filters.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{
+///     filter::{EmptyFilter, IdentityFilter},
+///     Partial, SerializePartial,
+/// };
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42 };
+///
+/// let json = serde_json::to_value(&Partial { value: &user, filter: IdentityFilter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+///
+/// let json = serde_json::to_value(&Partial { value: &user, filter: EmptyFilter }).unwrap();
+/// assert_eq!(json, serde_json::json!({}));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdentityFilter;
+
+impl<T: ?Sized> SerializeFilter<T> for IdentityFilter {
+    fn skip(&self, _field: Field<'_, T>) -> bool {
+        false
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        len
+    }
+
+    fn exact_len(&self) -> bool {
+        true
+    }
+}
+
+/// A [`SerializeFilter`] which skips all the fields.
+///
+/// See [`IdentityFilter`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmptyFilter;
+
+impl<T: ?Sized> SerializeFilter<T> for EmptyFilter {
+    fn skip(&self, _field: Field<'_, T>) -> bool {
+        true
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        Some(0)
+    }
+
+    fn exact_len(&self) -> bool {
+        true
+    }
+}
+
+/// A [`SerializeFilter`] which only serializes the fields stored in a fixed size array.
+///
+/// The filter owns its fields and never allocates, which makes it usable without `alloc`
+/// and in `static` declarations. Fields should exist on `T` for the filtered length to be accurate.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::ArrayFilter, Field, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// static PUBLIC: ArrayFilter<User, 2> = ArrayFilter::new([Field::new("name"), Field::new("age")]);
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let json = serde_json::to_value(&Partial { value: &user, filter: &PUBLIC }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+/// ```
+pub struct ArrayFilter<T: ?Sized, const N: usize> {
+    fields: [Field<'static, T>; N],
+}
+
+impl<T: ?Sized, const N: usize> SerializeFilter<T> for ArrayFilter<T, N> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        !self.fields.iter().any(|f| f.name() == field.name())
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        let unique = self
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(i, f)| !self.fields[..*i].iter().any(|g| g.name() == f.name()))
+            .count();
+        len.map(|len| len.min(unique))
+    }
+}
+
+impl<T: ?Sized, const N: usize> ArrayFilter<T, N> {
+    /// Creates a filter which only serializes the provided fields.
+    pub const fn new(fields: [Field<'static, T>; N]) -> Self {
+        Self { fields }
+    }
+
+    /// Returns the serialized fields.
+    pub const fn fields(&self) -> &[Field<'static, T>; N] {
+        &self.fields
+    }
+}
+
+impl<T: ?Sized, const N: usize> Clone for ArrayFilter<T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized, const N: usize> Copy for ArrayFilter<T, N> {}
+impl<T: ?Sized, const N: usize> fmt::Debug for ArrayFilter<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArrayFilter").field(&self.fields).finish()
+    }
+}
+impl<T: ?Sized, const N: usize> PartialEq for ArrayFilter<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields
+    }
+}
+impl<T: ?Sized, const N: usize> Eq for ArrayFilter<T, N> {}
+impl<T: ?Sized, const N: usize> hash::Hash for ArrayFilter<T, N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.fields.hash(state);
+    }
+}
+
+/// Counts the fields a combined filter doesn't skip, given the length of the filters it combines if both are known.
+fn combined_len<'a, T>(len: Option<usize>, skip: impl Fn(Field<'_, T>) -> bool) -> Option<usize>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    len?;
+    T::FIELD_COUNT?;
+    // the never serialized fields are counted like serde does, partials then discount them
+    let count = T::field_names()
+        .iter()
+        .chain(T::NEVER_FIELDS)
+        .filter(|name| !skip(Field::new(name)))
+        .count();
+    Some(count)
+}
+
+/// A [`SerializeFilter`] which only serializes the fields selected by both of the filters it combines.
+///
+/// The filtered length is only known when both filters know theirs and the type knows its [field count][SerializePartial::FIELD_COUNT]. If both filters provide a [`nested`][SerializeFilter::nested] filter for a field, the first one is used.
+pub struct AndFilter<T: ?Sized, F, G> {
+    first: F,
+    second: G,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<'a, T, F, G> SerializeFilter<T> for AndFilter<T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.first.skip(field) || self.second.skip(field)
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        combined_len(
+            self.first
+                .filtered_len(len)
+                .and(self.second.filtered_len(len)),
+            |field| self.skip(field),
+        )
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.first
+            .nested(field)
+            .or_else(|| self.second.nested(field))
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.first.skip_hr(field, human_readable) || self.second.skip_hr(field, human_readable)
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        combined_len(
+            self.first
+                .filtered_len_hr(len, human_readable)
+                .and(self.second.filtered_len_hr(len, human_readable)),
+            |field| self.skip_hr(field, human_readable),
+        )
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.first
+            .nested_hr(field, human_readable)
+            .or_else(|| self.second.nested_hr(field, human_readable))
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        self.first.skip_at(field, position, human_readable)
+            || self.second.skip_at(field, position, human_readable)
+    }
+
+    fn exact_len(&self) -> bool {
+        self.first.exact_len() && self.second.exact_len()
+    }
+}
+
+impl<T: ?Sized, F, G> AndFilter<T, F, G> {
+    /// Creates a filter combining the two provided ones.
+    pub const fn new(first: F, second: G) -> Self {
+        Self {
+            first,
+            second,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the combined filters.
+    pub fn into_inner(self) -> (F, G) {
+        (self.first, self.second)
+    }
+}
+
+impl<T: ?Sized, F: Default, G: Default> Default for AndFilter<T, F, G> {
+    fn default() -> Self {
+        Self::new(F::default(), G::default())
+    }
+}
+impl<T: ?Sized, F: Clone, G: Clone> Clone for AndFilter<T, F, G> {
+    fn clone(&self) -> Self {
+        Self::new(self.first.clone(), self.second.clone())
+    }
+}
+impl<T: ?Sized, F: Copy, G: Copy> Copy for AndFilter<T, F, G> {}
+impl<T: ?Sized, F: fmt::Debug, G: fmt::Debug> fmt::Debug for AndFilter<T, F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AndFilter")
+            .field(&self.first)
+            .field(&self.second)
+            .finish()
+    }
+}
+impl<T: ?Sized, F: PartialEq, G: PartialEq> PartialEq for AndFilter<T, F, G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.first == other.first && self.second == other.second
+    }
+}
+impl<T: ?Sized, F: Eq, G: Eq> Eq for AndFilter<T, F, G> {}
+impl<T: ?Sized, F: hash::Hash, G: hash::Hash> hash::Hash for AndFilter<T, F, G> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.first.hash(state);
+        self.second.hash(state);
+    }
+}
+
+/// A [`SerializeFilter`] which serializes the fields selected by either of the filters it combines.
+///
+/// The filtered length is only known when both filters know theirs and the type knows its [field count][SerializePartial::FIELD_COUNT]. If both filters provide a [`nested`][SerializeFilter::nested] filter for a field, the first one is used.
+pub struct OrFilter<T: ?Sized, F, G> {
+    first: F,
+    second: G,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<'a, T, F, G> SerializeFilter<T> for OrFilter<T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.first.skip(field) && self.second.skip(field)
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        combined_len(
+            self.first
+                .filtered_len(len)
+                .and(self.second.filtered_len(len)),
+            |field| self.skip(field),
+        )
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.first
+            .nested(field)
+            .or_else(|| self.second.nested(field))
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.first.skip_hr(field, human_readable) && self.second.skip_hr(field, human_readable)
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        combined_len(
+            self.first
+                .filtered_len_hr(len, human_readable)
+                .and(self.second.filtered_len_hr(len, human_readable)),
+            |field| self.skip_hr(field, human_readable),
+        )
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.first
+            .nested_hr(field, human_readable)
+            .or_else(|| self.second.nested_hr(field, human_readable))
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        self.first.skip_at(field, position, human_readable)
+            && self.second.skip_at(field, position, human_readable)
+    }
+
+    fn exact_len(&self) -> bool {
+        self.first.exact_len() && self.second.exact_len()
+    }
+}
+
+impl<T: ?Sized, F, G> OrFilter<T, F, G> {
+    /// Creates a filter combining the two provided ones.
+    pub const fn new(first: F, second: G) -> Self {
+        Self {
+            first,
+            second,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the combined filters.
+    pub fn into_inner(self) -> (F, G) {
+        (self.first, self.second)
+    }
+}
+
+impl<T: ?Sized, F: Default, G: Default> Default for OrFilter<T, F, G> {
+    fn default() -> Self {
+        Self::new(F::default(), G::default())
+    }
+}
+impl<T: ?Sized, F: Clone, G: Clone> Clone for OrFilter<T, F, G> {
+    fn clone(&self) -> Self {
+        Self::new(self.first.clone(), self.second.clone())
+    }
+}
+impl<T: ?Sized, F: Copy, G: Copy> Copy for OrFilter<T, F, G> {}
+impl<T: ?Sized, F: fmt::Debug, G: fmt::Debug> fmt::Debug for OrFilter<T, F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OrFilter")
+            .field(&self.first)
+            .field(&self.second)
+            .finish()
+    }
+}
+impl<T: ?Sized, F: PartialEq, G: PartialEq> PartialEq for OrFilter<T, F, G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.first == other.first && self.second == other.second
+    }
+}
+impl<T: ?Sized, F: Eq, G: Eq> Eq for OrFilter<T, F, G> {}
+impl<T: ?Sized, F: hash::Hash, G: hash::Hash> hash::Hash for OrFilter<T, F, G> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.first.hash(state);
+        self.second.hash(state);
+    }
+}
+
+/// A [`SerializeFilter`] which uses a fallback filter when its primary filter doesn't select any field.
+///
+/// The primary filter is considered empty if its [`filtered_len`][SerializeFilter::filtered_len] only accounts for the fields marked with `#[serde_partial(always)]`.
+/// This is decided once when the filter is created, so filters with an unknown length never fall back.
+/// A common use is to serialize all the fields when a request doesn't select any.
+///
+/// See [`Partial::or_fallback`][crate::Partial::or_fallback].
+pub struct FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    primary: F,
+    fallback: G,
+    use_fallback: bool,
+    _ty: PhantomData<&'a T>,
+}
+
+impl<'a, T, F, G> SerializeFilter<T> for FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        if self.use_fallback {
+            self.fallback.skip(field)
+        } else {
+            self.primary.skip(field)
+        }
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        if self.use_fallback {
+            self.fallback.filtered_len(len)
+        } else {
+            self.primary.filtered_len(len)
+        }
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        if self.use_fallback {
+            self.fallback.nested(field)
+        } else {
+            self.primary.nested(field)
+        }
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        if self.use_fallback {
+            self.fallback.skip_hr(field, human_readable)
+        } else {
+            self.primary.skip_hr(field, human_readable)
+        }
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        if self.use_fallback {
+            self.fallback.filtered_len_hr(len, human_readable)
+        } else {
+            self.primary.filtered_len_hr(len, human_readable)
+        }
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        if self.use_fallback {
+            self.fallback.nested_hr(field, human_readable)
+        } else {
+            self.primary.nested_hr(field, human_readable)
+        }
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        if self.use_fallback {
+            self.fallback.skip_at(field, position, human_readable)
+        } else {
+            self.primary.skip_at(field, position, human_readable)
+        }
+    }
+
+    fn exact_len(&self) -> bool {
+        if self.use_fallback {
+            self.fallback.exact_len()
+        } else {
+            self.primary.exact_len()
+        }
+    }
+}
+
+impl<'a, T, F, G> FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    /// Creates a filter which uses `fallback` if `primary` doesn't select any field.
+    pub fn new(primary: F, fallback: G) -> Self {
+        let use_fallback = primary.filtered_len(T::FIELD_COUNT) == Some(T::ALWAYS_FIELDS.len());
+        Self {
+            primary,
+            fallback,
+            use_fallback,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns whether the fallback filter is used.
+    pub fn uses_fallback(&self) -> bool {
+        self.use_fallback
+    }
+
+    /// Returns the primary and fallback filters.
+    pub fn into_inner(self) -> (F, G) {
+        (self.primary, self.fallback)
+    }
+}
+
+impl<'a, T, F, G> Clone for FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: Clone,
+    G: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            primary: self.primary.clone(),
+            fallback: self.fallback.clone(),
+            use_fallback: self.use_fallback,
+            _ty: PhantomData,
+        }
+    }
+}
+impl<'a, T, F, G> Copy for FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: Copy,
+    G: Copy,
+{
+}
+
+impl<'a, T, F, G> fmt::Debug for FallbackFilter<'a, T, F, G>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: fmt::Debug,
+    G: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FallbackFilter")
+            .field("primary", &self.primary)
+            .field("fallback", &self.fallback)
+            .field("use_fallback", &self.use_fallback)
+            .finish()
+    }
+}
+
+/// A [`SerializeFilter`] which picks one of two filters depending on whether the serializer is human readable.
+///
+/// Serializers such as JSON are human readable while compact binary formats usually aren't, see [`Serializer::is_human_readable`][serde::Serializer::is_human_readable].
+/// When the filter is used without a serializer, for example through [`skip`][SerializeFilter::skip], the human readable filter is used.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::HumanReadableFilter, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     debug_info: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", debug_info: "..." };
+/// let filter = HumanReadableFilter::new(
+///     user.with_fields(|u| [u.name, u.debug_info]).filter,
+///     user.with_fields(|u| [u.name]).filter,
+/// );
+/// let partial = Partial { value: &user, filter };
+///
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "debug_info": "..." }));
+/// ```
+///
+/// The filters of nested values are picked the same way.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::HumanReadableFilter, Field, Partial, SerializePartial};
+/// use serde_test::{Configure, Token};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Address {
+///     city: &'static str,
+///     street: &'static str,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     #[serde_partial(nested)]
+///     address: Address,
+/// }
+///
+/// let user = User { address: Address { city: "Paris", street: "Rue de Rivoli" } };
+/// let filter = HumanReadableFilter::new(
+///     user.with_fields(|_| [Field::new("address.city"), Field::new("address.street")]).filter,
+///     user.with_fields(|_| [Field::new("address.city")]).filter,
+/// );
+/// let partial = Partial { value: &user, filter };
+///
+/// serde_test::assert_ser_tokens(
+///     &(&partial).compact(),
+///     &[
+///         Token::Struct { name: "User", len: 1 },
+///         Token::Str("address"),
+///         Token::Struct { name: "Address", len: 1 },
+///         Token::Str("city"),
+///         Token::Str("Paris"),
+///         Token::StructEnd,
+///         Token::StructEnd,
+///     ],
+/// );
+/// ```
+pub struct HumanReadableFilter<T: ?Sized, F, G> {
+    hr: F,
+    compact: G,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T, F, G> SerializeFilter<T> for HumanReadableFilter<T, F, G>
+where
+    T: ?Sized,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.hr.skip(field)
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.hr.filtered_len(len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.hr.nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        if human_readable {
+            self.hr.skip_hr(field, human_readable)
+        } else {
+            self.compact.skip_hr(field, human_readable)
+        }
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        if human_readable {
+            self.hr.filtered_len_hr(len, human_readable)
+        } else {
+            self.compact.filtered_len_hr(len, human_readable)
+        }
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        if human_readable {
+            self.hr.nested_hr(field, human_readable)
+        } else {
+            self.compact.nested_hr(field, human_readable)
+        }
+    }
+
+    fn exact_len(&self) -> bool {
+        self.hr.exact_len() && self.compact.exact_len()
+    }
+}
+
+impl<T: ?Sized, F, G> HumanReadableFilter<T, F, G> {
+    /// Creates a filter using `hr` for human readable serializers and `compact` for the other ones.
+    pub const fn new(hr: F, compact: G) -> Self {
+        Self {
+            hr,
+            compact,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the human readable and compact filters.
+    pub fn into_inner(self) -> (F, G) {
+        (self.hr, self.compact)
+    }
+}
+
+impl<T: ?Sized, F: Default, G: Default> Default for HumanReadableFilter<T, F, G> {
+    fn default() -> Self {
+        Self::new(F::default(), G::default())
+    }
+}
+impl<T: ?Sized, F: Clone, G: Clone> Clone for HumanReadableFilter<T, F, G> {
+    fn clone(&self) -> Self {
+        Self::new(self.hr.clone(), self.compact.clone())
+    }
+}
+impl<T: ?Sized, F: Copy, G: Copy> Copy for HumanReadableFilter<T, F, G> {}
+impl<T: ?Sized, F: fmt::Debug, G: fmt::Debug> fmt::Debug for HumanReadableFilter<T, F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HumanReadableFilter")
+            .field("hr", &self.hr)
+            .field("compact", &self.compact)
+            .finish()
+    }
+}
+impl<T: ?Sized, F: PartialEq, G: PartialEq> PartialEq for HumanReadableFilter<T, F, G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hr == other.hr && self.compact == other.compact
+    }
+}
+impl<T: ?Sized, F: Eq, G: Eq> Eq for HumanReadableFilter<T, F, G> {}
+impl<T: ?Sized, F: hash::Hash, G: hash::Hash> hash::Hash for HumanReadableFilter<T, F, G> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.hr.hash(state);
+        self.compact.hash(state);
+    }
+}
+
+/// A [`SerializeFilter`] which only serializes the first `limit` fields.
+///
+/// The filter counts the fields it lets through using a [`Cell`], which means it isn't [`Sync`]
+/// and can't be shared between threads while serializing.
+/// The count isn't reset after serializing, so [`reset`][LimitFilter::reset] should be called before reusing the filter.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::LimitFilter, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let mut partial = Partial { value: &user, filter: LimitFilter::new(2) };
+///
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+///
+/// partial.filter.reset();
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+/// ```
+pub struct LimitFilter<T: ?Sized> {
+    limit: usize,
+    seen: Cell<usize>,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> SerializeFilter<T> for LimitFilter<T> {
+    fn skip(&self, _field: Field<'_, T>) -> bool {
+        let seen = self.seen.get();
+        if seen < self.limit {
+            self.seen.set(seen + 1);
+            false
+        } else {
+            true
+        }
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        len.map(|len| len.min(self.limit))
+    }
+}
+
+impl<T: ?Sized> LimitFilter<T> {
+    /// Creates a filter which only serializes the first `limit` fields.
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            seen: Cell::new(0),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the maximum number of serialized fields.
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Resets the count of serialized fields so the filter can be reused.
+    pub fn reset(&mut self) {
+        *self.seen.get_mut() = 0;
+    }
+}
+
+impl<T: ?Sized> Clone for LimitFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            limit: self.limit,
+            seen: self.seen.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+impl<T: ?Sized> fmt::Debug for LimitFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LimitFilter")
+            .field("limit", &self.limit)
+            .field("seen", &self.seen.get())
+            .finish()
+    }
+}
+
+/// Trait implemented by filters which update some state as they decide which fields to skip.
+///
+/// Unlike [`SerializeFilter::skip`], [`StatefulFilter::skip`] takes `&mut self`,
+/// which makes it possible to make decisions based on the fields seen so far without interior mutability.
+/// Stateful filters are used by wrapping them in a [`StatefulAdapter`].
+///
+/// Every field is seen by the filter in order, including the ones marked with `#[serde_partial(always)]` or `#[serde_partial(never)]`,
+/// unless they are skipped by serde itself.
+pub trait StatefulFilter<T: ?Sized> {
+    /// Returns `true` if the field should be skipped, updating the state of the filter.
+    fn skip(&mut self, field: Field<'_, T>) -> bool;
+
+    /// Resets the state of the filter so it can be reused for another value.
+    ///
+    /// Does nothing by default.
+    fn reset(&mut self) {}
+}
+
+impl<T, S> StatefulFilter<T> for &mut S
+where
+    T: ?Sized,
+    S: ?Sized + StatefulFilter<T>,
+{
+    fn skip(&mut self, field: Field<'_, T>) -> bool {
+        (**self).skip(field)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// A [`SerializeFilter`] which holds a [`StatefulFilter`] and lends it mutably while serializing.
+///
+/// The state is kept in a [`RefCell`], which means the filter isn't [`Sync`] and can't be shared between threads.
+/// The filtered length can't be known ahead of time.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{
+///     filter::{StatefulAdapter, StatefulFilter},
+///     Field, Partial, SerializePartial,
+/// };
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// /// Stops serializing fields once their names exceed a budget.
+/// struct NameBudget {
+///     budget: usize,
+///     left: usize,
+/// }
+///
+/// impl StatefulFilter<User> for NameBudget {
+///     fn skip(&mut self, field: Field<'_, User>) -> bool {
+///         match self.left.checked_sub(field.name().len()) {
+///             Some(left) => {
+///                 self.left = left;
+///                 false
+///             }
+///             None => true,
+///         }
+///     }
+///
+///     fn reset(&mut self) {
+///         self.left = self.budget;
+///     }
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let mut partial = Partial {
+///     value: &user,
+///     filter: StatefulAdapter::new(NameBudget { budget: 8, left: 8 }),
+/// };
+///
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+///
+/// partial.filter.reset();
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+/// ```
+pub struct StatefulAdapter<T: ?Sized, S> {
+    state: RefCell<S>,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T, S> SerializeFilter<T> for StatefulAdapter<T, S>
+where
+    T: ?Sized,
+    S: StatefulFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.state.borrow_mut().skip(field)
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+}
+
+impl<T: ?Sized, S> StatefulAdapter<T, S> {
+    /// Wraps a stateful filter.
+    pub const fn new(state: S) -> Self {
+        Self {
+            state: RefCell::new(state),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns a mutable reference to the stateful filter.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.state.get_mut()
+    }
+
+    /// Returns the stateful filter.
+    pub fn into_inner(self) -> S {
+        self.state.into_inner()
+    }
+
+    /// Resets the stateful filter so it can be reused.
+    ///
+    /// See [`StatefulFilter::reset`].
+    pub fn reset(&mut self)
+    where
+        S: StatefulFilter<T>,
+    {
+        self.state.get_mut().reset()
+    }
+}
+
+impl<T: ?Sized, S: Clone> Clone for StatefulAdapter<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+impl<T: ?Sized, S: fmt::Debug> fmt::Debug for StatefulAdapter<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StatefulAdapter").field(&self.state).finish()
+    }
+}
+impl<T: ?Sized, S: Default> Default for StatefulAdapter<T, S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+/// A [`SerializeFilter`] which serializes the fields matching a predicate.
+///
+/// Fields marked with `#[serde_partial(always)]` or `#[serde_partial(never)]` keep their behavior regardless of the predicate.
+/// The filtered length can't be known ahead of time.
+///
+/// See [`SerializePartial::with_fields_if`].
+pub struct ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    predicate: P,
+    _ty: PhantomData<&'a T>,
+}
+
+impl<'a, T, P> SerializeFilter<T> for ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+    P: Fn(Field<'_, T>) -> bool,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            false
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            true
+        } else {
+            !(self.predicate)(field)
+        }
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+}
+
+impl<'a, T, P> ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+    P: Fn(Field<'_, T>) -> bool,
+{
+    /// Creates a filter which serializes the fields matching the provided predicate.
+    pub fn new(predicate: P) -> Self {
+        Self {
+            predicate,
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, P> Clone for ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            predicate: self.predicate.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+impl<'a, T, P> Copy for ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+    P: Copy,
+{
+}
+
+impl<'a, T, P> fmt::Debug for ClosureFilter<'a, T, P>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ClosureFilter")
+            .field(&core::any::type_name::<P>())
+            .finish()
+    }
+}
+
+/// A [`SerializeFilter`] which resolves field names to the ones of the type ignoring ASCII case before passing them to the filter it wraps.
+///
+/// This is useful when the selected names come from external sources with inconsistent capitalization, such as HTTP headers.
+/// Names matching one of the [field names][SerializePartial::field_names] of the type are replaced with the exact serde name,
+/// so the wrapped filter can be the one generated by the derive macro. Other names are passed through unchanged.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::CaseInsensitiveFilter, Field, Partial, SerializeFilter, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { full_name: "John Doe", age: 42 };
+/// let filter = CaseInsensitiveFilter::new(<User as SerializePartial>::Filter::from_names(["fullName"]).unwrap());
+/// assert!(!filter.skip(Field::new("FULLNAME")));
+/// assert!(filter.skip(Field::new("AGE")));
+///
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe" }));
+/// ```
+pub struct CaseInsensitiveFilter<T: ?Sized, F> {
+    filter: F,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<'a, T, F> SerializeFilter<T> for CaseInsensitiveFilter<T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.filter.skip(canonical_field(field))
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.filter.filtered_len(len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(canonical_field(field))
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.filter.skip_hr(canonical_field(field), human_readable)
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        self.filter.filtered_len_hr(len, human_readable)
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.filter
+            .nested_hr(canonical_field(field), human_readable)
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        self.filter
+            .skip_at(canonical_field(field), position, human_readable)
+    }
+
+    fn exact_len(&self) -> bool {
+        self.filter.exact_len()
+    }
+}
+
+/// Replaces the name of the field with the matching field name of the type, ignoring ASCII case.
+fn canonical_field<'a, 'f, T>(field: Field<'f, T>) -> Field<'f, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    match T::field_names()
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(field.name))
+    {
+        Some(name) => Field { name, ..field },
+        None => field,
+    }
+}
+
+impl<T: ?Sized, F> CaseInsensitiveFilter<T, F> {
+    /// Creates a case insensitive filter wrapping the provided one.
+    pub const fn new(filter: F) -> Self {
+        Self {
+            filter,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the wrapped filter.
+    pub const fn get(&self) -> &F {
+        &self.filter
+    }
+
+    /// Returns the wrapped filter.
+    pub fn into_inner(self) -> F {
+        self.filter
+    }
+}
+
+impl<T: ?Sized, F: Default> Default for CaseInsensitiveFilter<T, F> {
+    fn default() -> Self {
+        Self::new(F::default())
+    }
+}
+impl<T: ?Sized, F: Clone> Clone for CaseInsensitiveFilter<T, F> {
+    fn clone(&self) -> Self {
+        Self::new(self.filter.clone())
+    }
+}
+impl<T: ?Sized, F: Copy> Copy for CaseInsensitiveFilter<T, F> {}
+impl<T: ?Sized, F: fmt::Debug> fmt::Debug for CaseInsensitiveFilter<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CaseInsensitiveFilter")
+            .field(&self.filter)
+            .finish()
+    }
+}
+impl<T: ?Sized, F: PartialEq> PartialEq for CaseInsensitiveFilter<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+    }
+}
+impl<T: ?Sized, F: Eq> Eq for CaseInsensitiveFilter<T, F> {}
+impl<T: ?Sized, F: hash::Hash> hash::Hash for CaseInsensitiveFilter<T, F> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.filter.hash(state);
+    }
+}
+
+/// A [`SerializeFilter`] built from names which are matched against the field names ignoring case, `_` and `-`.
+///
+/// This is useful when clients don't use the same naming convention as serde, for example sending `full_name` for a field serialized as `fullName`.
+/// Requested names are resolved to the field names listed by [`SerializePartial::field_names`] when building the filter,
+/// so unknown names are still reported and serializing doesn't compare names any more than usual.
+/// A requested name selects every field it matches.
+///
+/// Fields marked with `#[serde_partial(always)]` or `#[serde_partial(never)]` keep their behavior regardless of the requested names.
+/// The filtered length can't be known ahead of time.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::FuzzyNameFilter, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     date_of_birth: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { full_name: "John Doe", date_of_birth: "1980-01-01", age: 42 };
+/// let filter = FuzzyNameFilter::from_names(["full_name", "DATE-OF-BIRTH"]).unwrap();
+///
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe", "dateOfBirth": "1980-01-01" }));
+///
+/// let err = FuzzyNameFilter::<User>::from_names(["email"]).unwrap_err();
+/// assert_eq!(err.name(), "email");
+/// ```
+#[cfg(feature = "alloc")]
+pub struct FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    names: alloc::collections::BTreeSet<&'static str>,
+    _ty: PhantomData<&'a T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> SerializeFilter<T> for FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            false
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            true
+        } else {
+            !self.names.contains(field.name())
+        }
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    /// Creates a filter which doesn't select any field.
+    pub fn new() -> Self {
+        Self {
+            names: alloc::collections::BTreeSet::new(),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Creates a filter selecting the fields matching the provided names.
+    ///
+    /// Returns an error for the first name which doesn't match any field.
+    pub fn from_names<'n, I>(names: I) -> Result<Self, UnknownFieldError<'n>>
+    where
+        I: IntoIterator<Item = &'n str>,
+    {
+        let mut filter = Self::new();
+        for name in names {
+            filter.select(name)?;
+        }
+        Ok(filter)
+    }
+
+    /// Selects the fields matching the provided name.
+    pub fn select<'n>(&mut self, name: &'n str) -> Result<(), UnknownFieldError<'n>> {
+        let expected = T::field_names();
+        let mut found = false;
+        for field in expected {
+            if Self::matches(name, field) {
+                self.names.insert(field);
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(UnknownFieldError::with_expected(name, expected))
+        }
+    }
+
+    /// Returns whether two names are equal when ignoring case, `_` and `-`.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{filter::FuzzyNameFilter, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// # }
+    /// #
+    /// assert!(FuzzyNameFilter::<User>::matches("full_name", "fullName"));
+    /// assert!(FuzzyNameFilter::<User>::matches("Full-Name", "FULL_NAME"));
+    /// assert!(!FuzzyNameFilter::<User>::matches("full_name", "name"));
+    /// ```
+    pub fn matches(requested: &str, name: &str) -> bool {
+        fn normalize(name: &str) -> impl Iterator<Item = char> + '_ {
+            name.chars()
+                .filter(|c| *c != '_' && *c != '-')
+                .flat_map(char::to_lowercase)
+        }
+        normalize(requested).eq(normalize(name))
+    }
+
+    /// Returns the names of the selected fields.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.names.iter().copied()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> Default for FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<'a, T> Clone for FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            names: self.names.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<'a, T> fmt::Debug for FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FuzzyNameFilter").field(&self.names).finish()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<'a, T> PartialEq for FuzzyNameFilter<'a, T>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.names == other.names
+    }
+}
+#[cfg(feature = "alloc")]
+impl<'a, T> Eq for FuzzyNameFilter<'a, T> where T: ?Sized + SerializePartial<'a> {}
+
+/// A [`SerializeFilter`] which caches the decisions of the filter it wraps.
+///
+/// The decision for each field is computed once at construction using the ordered field list of the type.
+/// Since fields are serialized in the same order, the filter remembers the position of the last field
+/// and looking up the next one is usually a single comparison instead of a search, which makes it well suited to serializing many values with the same selection.
+///
+/// The position is stored in a [`Cell`], which means the filter isn't [`Sync`].
+/// Names which aren't part of the field list are passed to the wrapped filter, which is also used for nested filters.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::CachedFilter, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// let users = vec![User { name: "John Doe", age: 42 }, User { name: "Jane Doe", age: 43 }];
+/// let filter = CachedFilter::new(users[0].with_fields(|u| [u.name]).filter);
+///
+/// let partials = users
+///     .iter()
+///     .map(|user| serde_partial::Partial { value: user, filter: &filter })
+///     .collect::<Vec<_>>();
+/// let json = serde_json::to_value(&partials).unwrap();
+/// assert_eq!(json, serde_json::json!([{ "name": "John Doe" }, { "name": "Jane Doe" }]));
+/// ```
+#[cfg(feature = "alloc")]
+pub struct CachedFilter<'a, T, F = <T as SerializePartial<'a>>::Filter>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    filter: F,
+    names: &'static [&'static str],
+    keep: alloc::vec::Vec<bool>,
+    position: Cell<usize>,
+    _ty: PhantomData<&'a T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, F> CachedFilter<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    /// Creates a filter caching the decisions of the provided one.
+    pub fn new(filter: F) -> Self {
+        let names = T::field_names();
+        let keep = names
+            .iter()
+            .map(|name| !filter.skip(Field::new(name)))
+            .collect();
+        Self {
+            filter,
+            names,
+            keep,
+            position: Cell::new(0),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped filter.
+    pub fn into_inner(self) -> F {
+        self.filter
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        let position = self.position.get();
+        if self.names.get(position) == Some(&name) {
+            Some(position)
+        } else if self.names.first() == Some(&name) {
+            Some(0)
+        } else {
+            self.names.iter().position(|n| *n == name)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, F> SerializeFilter<T> for CachedFilter<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        match self.index(field.name()) {
+            Some(index) => {
+                self.position.set(index + 1);
+                !self.keep[index]
+            }
+            None => self.filter.skip(field),
+        }
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.filter.filtered_len(len)
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field)
+    }
+
+    fn exact_len(&self) -> bool {
+        self.filter.exact_len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, F> fmt::Debug for CachedFilter<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedFilter")
+            .field("filter", &self.filter)
+            .field("keep", &self.keep)
+            .finish()
+    }
+}
+
+/// A [`SerializeFilter`] which only serializes the fields yielded by an iterator.
+///
+/// The iterator is only consumed the first time the filter is used, which defers the cost of producing the fields until serialization.
+/// The yielded names are then stored and reused, so the filter can serialize any number of values.
+/// This relies on a [`RefCell`][core::cell::RefCell], which means the filter isn't [`Sync`] and can't be shared between threads.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::IterFilter, Field, Partial, SerializeFilter, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let requested = "name,email";
+/// let filter = IterFilter::new(requested.split(',').map(Field::new));
+/// assert!(!filter.is_initialized());
+///
+/// let json = serde_json::to_value(&Partial { value: &user, filter: &filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com" }));
+/// assert!(filter.is_initialized());
+/// // the length never exceeds the number of fields, even when more names are requested
+/// assert_eq!(filter.filtered_len(Some(1)), Some(1));
+/// ```
+#[cfg(feature = "alloc")]
+pub struct IterFilter<T: ?Sized, I> {
+    iter: core::cell::RefCell<Option<I>>,
+    names: core::cell::RefCell<alloc::collections::BTreeSet<&'static str>>,
+    _ty: PhantomData<fn(&T)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T, I> IterFilter<T, I>
+where
+    T: ?Sized,
+    I: Iterator<Item = Field<'static, T>>,
+{
+    /// Creates a filter which only serializes the fields yielded by the iterator.
+    pub fn new<J>(iter: J) -> Self
+    where
+        J: IntoIterator<IntoIter = I>,
+    {
+        Self {
+            iter: core::cell::RefCell::new(Some(iter.into_iter())),
+            names: core::cell::RefCell::new(alloc::collections::BTreeSet::new()),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns whether the iterator has already been consumed.
+    pub fn is_initialized(&self) -> bool {
+        self.iter.borrow().is_none()
+    }
+
+    fn init(&self) {
+        if let Some(iter) = self.iter.borrow_mut().take() {
+            self.names.borrow_mut().extend(iter.map(|f| f.name()));
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, I> SerializeFilter<T> for IterFilter<T, I>
+where
+    T: ?Sized,
+    I: Iterator<Item = Field<'static, T>>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.init();
+        !self.names.borrow().contains(field.name())
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        self.init();
+        len.map(|len| len.min(self.names.borrow().len()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized, I> fmt::Debug for IterFilter<T, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("IterFilter");
+        match self.iter.try_borrow() {
+            Ok(iter) if iter.is_some() => d.field("names", &format_args!("<pending>")),
+            _ => d.field("names", &self.names),
+        };
+        d.finish()
+    }
+}
+
+/// A changelog of the fields added and removed by each version of an API.
+///
+/// [`at_version`][VersionFilter::at_version] replays the changes up to a version to build the filter for that version.
+/// When using the derive macro, fields can be marked with `#[serde_partial(since = N)]` and `#[serde_partial(removed_in = N)]`,
+/// in which case the generated [`Fields`][SerializePartial::Fields] have a `versions` method returning the corresponding changelog.
+/// Fields without a `since` attribute are added in version `0`.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     #[serde_partial(removed_in = 3)]
+///     age: u8,
+///     #[serde_partial(since = 2)]
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let versions = <User as SerializePartial>::Fields::FIELDS.versions();
+///
+/// let filter = versions.at_version(1);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+///
+/// let filter = versions.at_version(2);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42, "email": "john.doe@example.com" }));
+///
+/// let filter = versions.at_version(3);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com" }));
+/// ```
+#[cfg(feature = "alloc")]
+pub struct VersionFilter<T: ?Sized> {
+    #[allow(clippy::type_complexity)]
+    changes: alloc::collections::BTreeMap<
+        u32,
+        (
+            alloc::vec::Vec<Field<'static, T>>,
+            alloc::vec::Vec<Field<'static, T>>,
+        ),
+    >,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> VersionFilter<T> {
+    /// Creates an empty changelog.
+    pub fn new() -> Self {
+        Self {
+            changes: alloc::collections::BTreeMap::new(),
+        }
+    }
+
+    /// Records fields added in the specified version.
+    pub fn added<I>(mut self, version: u32, fields: I) -> Self
+    where
+        I: IntoIterator<Item = Field<'static, T>>,
+    {
+        self.changes.entry(version).or_default().0.extend(fields);
+        self
+    }
+
+    /// Records fields removed in the specified version.
+    pub fn removed<I>(mut self, version: u32, fields: I) -> Self
+    where
+        I: IntoIterator<Item = Field<'static, T>>,
+    {
+        self.changes.entry(version).or_default().1.extend(fields);
+        self
+    }
+
+    /// Returns the filter serializing the fields present in the specified version.
+    ///
+    /// Within a single version, removals are applied after additions.
+    pub fn at_version(&self, version: u32) -> VersionedFilter<T> {
+        let mut names = alloc::collections::BTreeSet::new();
+        for (added, removed) in self.changes.range(..=version).map(|(_, c)| c) {
+            names.extend(added.iter().map(|f| f.name()));
+            for field in removed {
+                names.remove(field.name());
+            }
+        }
+        VersionedFilter {
+            version,
+            names,
+            _ty: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Default for VersionFilter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Clone for VersionFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            changes: self.changes.clone(),
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> fmt::Debug for VersionFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VersionFilter").field(&self.changes).finish()
+    }
+}
+
+/// A [`SerializeFilter`] which serializes the fields present in a version of an API.
+///
+/// Returned by [`VersionFilter::at_version`].
+#[cfg(feature = "alloc")]
+pub struct VersionedFilter<T: ?Sized> {
+    version: u32,
+    names: alloc::collections::BTreeSet<&'static str>,
+    _ty: PhantomData<fn(&T)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> VersionedFilter<T> {
+    /// Returns the version the filter was built for.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> SerializeFilter<T> for VersionedFilter<T> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        !self.names.contains(field.name())
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        Some(self.names.len())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Clone for VersionedFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            version: self.version,
+            names: self.names.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> fmt::Debug for VersionedFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedFilter")
+            .field("version", &self.version)
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+/// A [`SerializeFilter`] which wraps a boxed filter whose type is only known at runtime.
+///
+/// This is useful when the filter is chosen dynamically, for example by a middleware storing it in a request context.
+/// The filtered length can't be known ahead of time.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::DynamicFilter, Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42 };
+/// let filter = DynamicFilter::new(user.with_fields(|u| [u.name]).filter);
+/// let partial = Partial { value: &user, filter };
+/// let json = serde_json::to_value(&partial).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+/// ```
+#[cfg(feature = "alloc")]
+pub struct DynamicFilter<T: ?Sized> {
+    filter: alloc::boxed::Box<dyn SerializeFilter<T> + Send + Sync>,
+    type_name: &'static str,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> DynamicFilter<T> {
+    /// Creates a dynamic filter boxing the provided one.
+    pub fn new<F>(filter: F) -> Self
+    where
+        F: SerializeFilter<T> + Send + Sync + 'static,
+    {
+        Self {
+            filter: alloc::boxed::Box::new(filter),
+            type_name: core::any::type_name::<F>(),
+        }
+    }
+
+    /// Returns the type name of the wrapped filter.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> SerializeFilter<T> for DynamicFilter<T> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.filter.skip(field)
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.filter.skip_hr(field, human_readable)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> fmt::Debug for DynamicFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynamicFilter")
+            .field(&self.type_name)
+            .finish()
+    }
+}
+
+/// A [`SerializeFilter`] which wraps a reference counted filter whose type is only known at runtime.
+///
+/// Unlike [`DynamicFilter`], this filter can be cheaply cloned.
+/// The filtered length can't be known ahead of time.
+#[cfg(feature = "alloc")]
+pub struct ArcFilter<T: ?Sized> {
+    filter: alloc::sync::Arc<dyn SerializeFilter<T> + Send + Sync>,
+    type_name: &'static str,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> ArcFilter<T> {
+    /// Creates a dynamic filter wrapping the provided one in an [`Arc`][alloc::sync::Arc].
+    pub fn new<F>(filter: F) -> Self
+    where
+        F: SerializeFilter<T> + Send + Sync + 'static,
+    {
+        Self {
+            filter: alloc::sync::Arc::new(filter),
+            type_name: core::any::type_name::<F>(),
+        }
+    }
+
+    /// Returns the type name of the wrapped filter.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> SerializeFilter<T> for ArcFilter<T> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.filter.skip(field)
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        self.filter.skip_hr(field, human_readable)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> Clone for ArcFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            type_name: self.type_name,
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> fmt::Debug for ArcFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArcFilter").field(&self.type_name).finish()
+    }
 }
 
 /// A [`SerializeFilter`] which inverts the behavior of the filter it wraps.
+///
+/// The fields marked as always or never serialized keep their behavior.
+/// The filtered length is only known when the length of the wrapped filter is [exact][SerializeFilter::exact_len].
 pub struct InverseFilter<'a, T, F = <T as SerializePartial<'a>>::Filter>
 where
     T: ?Sized + SerializePartial<'a>,
@@ -28,15 +2325,62 @@ where
     F: SerializeFilter<T>,
 {
     fn skip(&self, field: Field<'_, T>) -> bool {
-        !self.filter.skip(field)
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            false
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            true
+        } else {
+            !self.filter.skip(field)
+        }
     }
 
     fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
-        match (len, self.filter.filtered_len(len)) {
-            (Some(len), Some(filtered_len)) => Some(len - filtered_len),
-            _ => None,
+        self.inverse_len(
+            len,
+            |len| self.filter.filtered_len(len),
+            |field| self.filter.skip(field),
+        )
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            false
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            true
+        } else {
+            !self.filter.skip_hr(field, human_readable)
+        }
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        self.inverse_len(
+            len,
+            |len| self.filter.filtered_len_hr(len, human_readable),
+            |field| self.filter.skip_hr(field, human_readable),
+        )
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.filter.nested_hr(field, human_readable)
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            false
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            true
+        } else {
+            !self.filter.skip_at(field, position, human_readable)
         }
     }
+
+    fn exact_len(&self) -> bool {
+        self.filter.exact_len()
+    }
 }
 
 impl<'a, T, F> InverseFilter<'a, T, F>
@@ -51,6 +2395,32 @@ where
             _ty: PhantomData,
         }
     }
+
+    /// Counts the always serialized fields along with the other fields skipped by the wrapped filter.
+    ///
+    /// This is only known when the length of the wrapped filter is [exact][SerializeFilter::exact_len].
+    fn inverse_len(
+        &self,
+        len: Option<usize>,
+        filtered_len: impl FnOnce(Option<usize>) -> Option<usize>,
+        skip: impl Fn(Field<'_, T>) -> bool,
+    ) -> Option<usize> {
+        if !self.filter.exact_len() {
+            return None;
+        }
+        // the never serialized fields aren't counted in `FIELD_COUNT` but are part of the length passed by serde
+        let (len, never) = match T::FIELD_COUNT {
+            Some(count) => (count, &[][..]),
+            None => (len?, T::NEVER_FIELDS),
+        };
+        let skipped = len.checked_sub(filtered_len(Some(len))?)?;
+        let ignored = T::ALWAYS_FIELDS
+            .iter()
+            .chain(never)
+            .filter(|name| skip(Field::new(name)))
+            .count();
+        Some(T::ALWAYS_FIELDS.len() + skipped.checked_sub(ignored)?)
+    }
 }
 
 impl<'a, T, F> Clone for InverseFilter<'a, T, F>
@@ -81,3 +2451,145 @@ where
         f.debug_tuple("InverseFilter").field(&self.filter).finish()
     }
 }
+
+/// Applies the always and never serialized fields of a type on top of any filter.
+///
+/// Partials wrap their filter in it, so filters which don't know about these fields, such as sets of names, still respect them.
+pub(crate) struct MarkedFilter<'f, T, F>
+where
+    T: ?Sized,
+    F: ?Sized,
+{
+    filter: &'f F,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<'f, 'a, T, F> MarkedFilter<'f, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: ?Sized + SerializeFilter<T>,
+{
+    pub(crate) fn new(filter: &'f F) -> Self {
+        Self {
+            filter,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns whether the field is skipped because of its marker, if it has one.
+    fn marked(field: Field<'_, T>) -> Option<bool> {
+        if field.matches_any(T::ALWAYS_FIELDS) {
+            Some(false)
+        } else if field.matches_any(T::NEVER_FIELDS) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Adjusts the length of the wrapped filter for the marked fields it selects or skips.
+    fn marked_len(
+        filtered_len: Option<usize>,
+        skip: impl Fn(Field<'_, T>) -> bool,
+    ) -> Option<usize> {
+        let always = T::ALWAYS_FIELDS
+            .iter()
+            .filter(|name| skip(Field::new(name)))
+            .count();
+        let never = T::NEVER_FIELDS
+            .iter()
+            .filter(|name| !skip(Field::new(name)))
+            .count();
+        (filtered_len? + always).checked_sub(never)
+    }
+}
+
+impl<'f, 'a, T, F> SerializeFilter<T> for MarkedFilter<'f, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: ?Sized + SerializeFilter<T>,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        Self::marked(field).unwrap_or_else(|| self.filter.skip(field))
+    }
+
+    fn filtered_len(&self, len: Option<usize>) -> Option<usize> {
+        Self::marked_len(self.filter.filtered_len(len), |field| {
+            self.filter.skip(field)
+        })
+    }
+
+    fn nested(&self, field: Field<'_, T>) -> Option<&dyn ErasedFilter> {
+        self.filter.nested(field)
+    }
+
+    fn skip_hr(&self, field: Field<'_, T>, human_readable: bool) -> bool {
+        Self::marked(field).unwrap_or_else(|| self.filter.skip_hr(field, human_readable))
+    }
+
+    fn filtered_len_hr(&self, len: Option<usize>, human_readable: bool) -> Option<usize> {
+        Self::marked_len(self.filter.filtered_len_hr(len, human_readable), |field| {
+            self.filter.skip_hr(field, human_readable)
+        })
+    }
+
+    fn nested_hr(&self, field: Field<'_, T>, human_readable: bool) -> Option<&dyn ErasedFilter> {
+        self.filter.nested_hr(field, human_readable)
+    }
+
+    fn skip_at(&self, field: Field<'_, T>, position: usize, human_readable: bool) -> bool {
+        Self::marked(field).unwrap_or_else(|| self.filter.skip_at(field, position, human_readable))
+    }
+
+    fn exact_len(&self) -> bool {
+        self.filter.exact_len()
+    }
+}
+
+/// Splits a value into two partial values, one serializing the fields selected by the filter and one serializing the other fields.
+///
+/// Both partial values are derived from clones of the same filter, the second one wrapping it in an [`InverseFilter`],
+/// so they are guaranteed to be complementary as long as the filter is deterministic.
+/// Fields marked with `#[serde_partial(always)]` are serialized by both, and fields marked with `#[serde_partial(never)]` by neither.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{filter::partition, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let filter = user.with_fields(|u| [u.name, u.email]).filter;
+/// let (included, excluded) = partition(&user, filter);
+///
+/// let json = serde_json::to_value(&included).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com" }));
+///
+/// let json = serde_json::to_value(&excluded).unwrap();
+/// assert_eq!(json, serde_json::json!({ "age": 42 }));
+/// ```
+pub fn partition<'a, T, F>(
+    value: &'a T,
+    filter: F,
+) -> (Partial<'a, T, F>, Partial<'a, T, InverseFilter<'a, T, F>>)
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T> + Clone,
+{
+    let included = Partial {
+        value,
+        filter: filter.clone(),
+    };
+    let excluded = Partial {
+        value,
+        filter: InverseFilter::new(filter),
+    };
+    (included, excluded)
+}
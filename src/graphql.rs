@@ -0,0 +1,216 @@
+//! GraphQL integration
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{
+//!     graphql::{parse_selection, ParseError},
+//!     Partial, SerializePartial,
+//! };
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct Customer {
+//!     name: &'static str,
+//!     email: &'static str,
+//! }
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct Order {
+//!     id: u32,
+//!     total: u32,
+//!     #[serde_partial(nested)]
+//!     customer: Customer,
+//! }
+//!
+//! let order = Order {
+//!     id: 1,
+//!     total: 350,
+//!     customer: Customer { name: "John Doe", email: "john.doe@example.com" },
+//! };
+//!
+//! let filter = parse_selection::<Order>("{ id customer { name } }").unwrap();
+//! let json = serde_json::to_value(&Partial { value: &order, filter }).unwrap();
+//! assert_eq!(
+//!     json,
+//!     serde_json::json!({ "id": 1, "customer": { "name": "John Doe" } })
+//! );
+//!
+//! assert!(parse_selection::<Order>("{ id discount }").is_err());
+//! assert!(parse_selection::<Order>("{ id total { amount } }").is_err());
+//! assert!(parse_selection::<Order>("{ id").is_err());
+//!
+//! // deeply nested selections are rejected as soon as they reach a field which isn't nested
+//! let hostile = format!("{{{}", "customer{".repeat(200_000));
+//! let err = parse_selection::<Order>(&hostile).unwrap_err();
+//! assert_eq!(err, ParseError::UnknownField("customer.customer".to_owned()));
+//! let hostile = format!("{{{}", "a{".repeat(200_000));
+//! let err = parse_selection::<Order>(&hostile).unwrap_err();
+//! assert_eq!(err, ParseError::UnknownField("a".to_owned()));
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, iter::Peekable, str::CharIndices};
+
+use crate::{filter::SelectFilter, SerializePartial};
+
+/// Error returned when a selection set can't be parsed or references unknown fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseError {
+    /// An unexpected character was found at the specified byte offset.
+    UnexpectedCharacter {
+        /// Byte offset of the character in the input.
+        position: usize,
+        /// The unexpected character.
+        character: char,
+    },
+    /// The input ended before the selection set was closed.
+    UnexpectedEnd,
+    /// The selection set references a field which doesn't exist.
+    ///
+    /// Nested fields are referenced by their dotted path.
+    UnknownField(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "unexpected character `{}` at position {}",
+                character, position
+            ),
+            ParseError::UnexpectedEnd => f.write_str("unexpected end of input"),
+            ParseError::UnknownField(name) => write!(f, "unknown field `{}`", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Parses a GraphQL selection set such as `{ name email }` into a filter for `T`.
+///
+/// Commas and whitespace are ignored like in GraphQL. Nested selection sets select the fields of nested values,
+/// which requires the field to be marked with `#[serde_partial(nested)]`.
+pub fn parse_selection<'a, T>(s: &str) -> Result<T::Filter, ParseError>
+where
+    T: ?Sized + SerializePartial<'a>,
+    T::Filter: SelectFilter,
+{
+    let mut parser = Parser {
+        input: s,
+        chars: s.char_indices().peekable(),
+    };
+    let mut filter = T::Filter::default();
+    let mut path = Vec::new();
+
+    parser.expect('{')?;
+    parser.selection_set(&mut filter, &mut path)?;
+    match parser.next_token() {
+        Some((position, character)) => Err(ParseError::UnexpectedCharacter {
+            position,
+            character,
+        }),
+        None => Ok(filter),
+    }
+}
+
+struct Parser<'s> {
+    input: &'s str,
+    chars: Peekable<CharIndices<'s>>,
+}
+
+impl<'s> Parser<'s> {
+    /// Parses the content of a selection set after its opening brace, including the closing brace.
+    fn selection_set<F>(
+        &mut self,
+        filter: &mut F,
+        path: &mut Vec<&'s str>,
+    ) -> Result<(), ParseError>
+    where
+        F: SelectFilter,
+    {
+        loop {
+            match self.peek_token() {
+                Some((_, '}')) => {
+                    self.chars.next();
+                    return Ok(());
+                }
+                Some(_) => {
+                    path.push(self.name()?);
+                    if let Some((_, '{')) = self.peek_token() {
+                        // only descend into fields which exist, so the depth is bounded by the nesting of the types
+                        let name = path.join(".");
+                        if F::default().select(&name).is_err() {
+                            return Err(ParseError::UnknownField(name));
+                        }
+                        self.chars.next();
+                        self.selection_set(filter, path)?;
+                    } else {
+                        let name = path.join(".");
+                        if filter.select(&name).is_err() {
+                            return Err(ParseError::UnknownField(name));
+                        }
+                    }
+                    path.pop();
+                }
+                None => return Err(ParseError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'s str, ParseError> {
+        let start = match self.chars.next() {
+            Some((i, c)) if c == '_' || c.is_ascii_alphabetic() => i,
+            Some((position, character)) => {
+                return Err(ParseError::UnexpectedCharacter {
+                    position,
+                    character,
+                })
+            }
+            None => return Err(ParseError::UnexpectedEnd),
+        };
+        let mut end = start + 1;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if c == '_' || c.is_ascii_alphanumeric() {
+                self.chars.next();
+                end = i + 1;
+            } else {
+                break;
+            }
+        }
+        Ok(&self.input[start..end])
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.next_token() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((position, character)) => Err(ParseError::UnexpectedCharacter {
+                position,
+                character,
+            }),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    fn next_token(&mut self) -> Option<(usize, char)> {
+        self.peek_token();
+        self.chars.next()
+    }
+
+    /// Skips ignored characters and returns the next significant one.
+    fn peek_token(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() || c == ',' {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        self.chars.peek().copied()
+    }
+}
@@ -0,0 +1,90 @@
+//! Support for [`heapless`](heapless_crate) collections.
+//!
+//! The filter for an `IndexMap<K, V, S, N>` is an `IndexSet<Field, S, N>` with the same capacity.
+//! Selected fields which don't match any key of the map are ignored, so the filter can never exceed its capacity.
+
+use hash32::{BuildHasher, Hash, Hasher};
+use heapless_crate::{IndexMap, IndexSet, Vec};
+use serde::Serialize;
+
+use crate::{Field, Partial, SerializeFilter, SerializePartial};
+
+impl<T: ?Sized> Hash for Field<'_, T> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.name.hash(state);
+    }
+}
+
+impl<'a, K, V, S, const N: usize> SerializePartial<'a> for IndexMap<K, V, S, N>
+where
+    K: Eq + Hash + AsRef<str> + Serialize + 'a,
+    V: Serialize + 'a,
+    S: BuildHasher + Default + 'a,
+{
+    type Fields = Vec<Field<'a, Self>, N>;
+    type Filter = IndexSet<Field<'a, Self>, S, N>;
+
+    fn with_fields<F, I>(&'a self, select: F) -> Partial<'a, Self>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>,
+    {
+        let fields: Self::Fields = self.keys().map(|k| Field::new(k.as_ref())).collect();
+        let mut filter = Self::Filter::default();
+        for field in select(fields) {
+            if self.keys().any(|k| k.as_ref() == field.name()) {
+                // can't fail since there are at most `N` distinct keys
+                let _ = filter.insert(field);
+            }
+        }
+        Partial {
+            value: self,
+            filter,
+        }
+    }
+}
+
+impl<'a, K, V, S, const N: usize> SerializeFilter<IndexMap<K, V, S, N>>
+    for IndexSet<Field<'a, IndexMap<K, V, S, N>>, S, N>
+where
+    S: BuildHasher,
+{
+    fn skip(&self, field: Field<'_, IndexMap<K, V, S, N>>) -> bool {
+        !self.contains(&field)
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Field, SerializePartial};
+
+    use heapless_crate::FnvIndexMap;
+
+    #[test]
+    fn fnv_index_map() {
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert("c", "d").unwrap();
+        map.insert("a", "b").unwrap();
+        map.insert("e", "f").unwrap();
+        let filtered = map.with_fields(|_| {
+            [
+                Field::new("x"),
+                Field::new("y"),
+                Field::new("a"),
+                Field::new("z"),
+                Field::new("c"),
+            ]
+        });
+        assert_eq!(
+            serde_json::to_string(&filtered).unwrap(),
+            r#"{"c":"d","a":"b"}"#
+        )
+    }
+}
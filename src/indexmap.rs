@@ -0,0 +1,69 @@
+use core::{
+    hash::{BuildHasher, Hash},
+    iter::Map,
+};
+
+use indexmap::{map::Keys, IndexMap, IndexSet};
+use serde::Serialize;
+
+use crate::{Field, Partial, SerializeFilter, SerializePartial};
+
+impl<'a, K, V, S> SerializePartial<'a> for IndexMap<K, V, S>
+where
+    K: Hash + Eq + AsRef<str> + Serialize + 'a,
+    V: Serialize + 'a,
+    S: BuildHasher + Default + 'a,
+{
+    #[allow(clippy::type_complexity)]
+    type Fields = Map<Keys<'a, K, V>, fn(&'a K) -> Field<'a, Self>>;
+    type Filter = IndexSet<Field<'a, Self>, S>;
+
+    fn with_fields<F, I>(&'a self, select: F) -> Partial<'a, Self>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>,
+    {
+        let fields: Self::Fields = self.keys().map(|k| Field::new(k.as_ref()));
+        let selected: Self::Filter = select(fields).into_iter().collect();
+        // fields which don't match any key would make the filtered length too large
+        let filter = self
+            .keys()
+            .map(|k| Field::new(k.as_ref()))
+            .filter(|f| selected.contains(f))
+            .collect();
+        Partial {
+            value: self,
+            filter,
+        }
+    }
+}
+
+impl<'a, K, V, S> SerializeFilter<IndexMap<K, V, S>> for IndexSet<Field<'a, IndexMap<K, V, S>>, S>
+where
+    S: BuildHasher,
+{
+    fn skip(&self, field: Field<'_, IndexMap<K, V, S>>) -> bool {
+        !self.contains(&field)
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Field, SerializePartial};
+
+    use indexmap::IndexMap;
+
+    #[test]
+    fn index_map() {
+        let map = IndexMap::<_, _>::from_iter([("c", "d"), ("a", "b"), ("e", "f")]);
+        let filtered = map.with_fields(|_| [Field::new("a"), Field::new("c")]);
+        assert_eq!(
+            serde_json::to_string(&filtered).unwrap(),
+            r#"{"c":"d","a":"b"}"#
+        )
+    }
+}
@@ -0,0 +1,219 @@
+//! `serde_json` convenience functions
+//!
+//! These functions select fields and serialize the value in a single call,
+//! which avoids having to bind the [`Partial`][crate::Partial] to a variable.
+//! [`Partial`][crate::Partial] also gets methods which are thin wrappers around the `serde_json` serialization functions.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{json, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//!     age: u8,
+//! }
+//!
+//! let user = User { name: "John Doe", age: 42 };
+//!
+//! let value = json::to_value_with(&user, |u| [u.name]).unwrap();
+//! assert_eq!(value, serde_json::json!({ "name": "John Doe" }));
+//!
+//! let string = json::to_string_with(&user, |u| [u.age]).unwrap();
+//! assert_eq!(string, r#"{"age":42}"#);
+//!
+//! let string = json::to_string_pretty_with(&user, |u| [u.age]).unwrap();
+//! assert_eq!(string, "{\n  \"age\": 42\n}");
+//!
+//! let partial = user.with_fields(|u| [u.name]);
+//! assert_eq!(partial.as_json_value(), serde_json::json!({ "name": "John Doe" }));
+//! assert_eq!(partial.as_json_string().unwrap(), r#"{"name":"John Doe"}"#);
+//! ```
+
+use alloc::string::String;
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Error, Value};
+
+use crate::{Field, Partial, SerializeFilter, SerializePartial};
+
+/// Serializes the selected fields of the value to a [`Value`].
+pub fn to_value_with<'a, T, F, I>(value: &'a T, select: F) -> Result<Value, Error>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: FnOnce(T::Fields) -> I,
+    I: IntoIterator<Item = Field<'a, T>>,
+{
+    serde_json::to_value(value.with_fields(select))
+}
+
+/// Serializes the selected fields of the value to a JSON string.
+pub fn to_string_with<'a, T, F, I>(value: &'a T, select: F) -> Result<String, Error>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: FnOnce(T::Fields) -> I,
+    I: IntoIterator<Item = Field<'a, T>>,
+{
+    serde_json::to_string(&value.with_fields(select))
+}
+
+/// Serializes the selected fields of the value to a pretty printed JSON string.
+pub fn to_string_pretty_with<'a, T, F, I>(value: &'a T, select: F) -> Result<String, Error>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: FnOnce(T::Fields) -> I,
+    I: IntoIterator<Item = Field<'a, T>>,
+{
+    serde_json::to_string_pretty(&value.with_fields(select))
+}
+
+/// Creates a new value by taking the selected fields from the partial value and the other fields from `base`.
+///
+/// Both values are serialized to JSON objects, the fields of the partial value replace the ones of `base`
+/// and the result is deserialized back into a `T`.
+/// This only works for types whose [`Deserialize`][serde::Deserialize] implementation accepts what their [`Serialize`][serde::Serialize] implementation produces,
+/// and whose top level fields are serialized as a JSON object.
+/// Selected fields skipped with `#[serde(skip_serializing_if = "...")]` keep the value of `base`,
+/// and any value which doesn't survive a round trip through JSON, such as a map with non string keys, makes merging fail.
+///
+/// ## Example
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_partial::{json, SerializePartial};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize, SerializePartial)]
+/// struct User {
+///     name: String,
+///     age: u8,
+/// }
+///
+/// let base = User { name: "John Doe".to_owned(), age: 42 };
+/// let update = User { name: "Jane Doe".to_owned(), age: 0 };
+///
+/// let merged = json::merge_into(&update.with_fields(|u| [u.name]), &base).unwrap();
+/// assert_eq!(merged, User { name: "Jane Doe".to_owned(), age: 42 });
+///
+/// let merged = update.without_fields(|u| [u.name]).merge(&base).unwrap();
+/// assert_eq!(merged, User { name: "John Doe".to_owned(), age: 0 });
+/// ```
+pub fn merge_into<'a, T, F>(partial: &Partial<'a, T, F>, base: &T) -> Result<T, Error>
+where
+    T: SerializePartial<'a> + DeserializeOwned,
+    F: SerializeFilter<T>,
+{
+    let mut merged = serde_json::to_value(base)?;
+    if let (Value::Object(merged), Value::Object(update)) =
+        (&mut merged, serde_json::to_value(partial)?)
+    {
+        merged.extend(update);
+    }
+    serde_json::from_value(merged)
+}
+
+impl<'a, T, F> Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    /// Creates a new value by taking the selected fields from the partial value and the other fields from `base`.
+    ///
+    /// See [`merge_into`].
+    pub fn merge(&self, base: &T) -> Result<T, Error>
+    where
+        T: Sized + DeserializeOwned,
+    {
+        merge_into(self, base)
+    }
+
+    /// Serializes the partial value to a [`Value`].
+    ///
+    /// ## Panics
+    ///
+    /// Panics if serialization fails, see [`try_as_json_value`][Partial::try_as_json_value].
+    pub fn as_json_value(&self) -> Value {
+        self.try_as_json_value()
+            .expect("failed to serialize partial value to JSON")
+    }
+
+    /// Serializes the partial value to a [`Value`], returning an error if serialization fails.
+    pub fn try_as_json_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self)
+    }
+
+    /// Serializes the partial value to a JSON string.
+    pub fn as_json_string(&self) -> Result<String, Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Serializes the partial value to a pretty printed JSON string.
+    pub fn as_json_string_pretty(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns a value whose [`Debug`][fmt::Debug] implementation prints the selected fields.
+    ///
+    /// See [`PartialDebug`].
+    pub fn debug(&self) -> PartialDebug<'_, 'a, T, F> {
+        PartialDebug { partial: self }
+    }
+}
+
+/// A [`Partial`] whose [`Debug`][fmt::Debug] implementation prints the selected fields.
+///
+/// The partial value is serialized to a [`Value`] which is then debug printed,
+/// so the output looks like the one of a JSON value rather than the one of the original type.
+/// This makes it possible to log partial values without choosing a format, at the cost of an intermediate allocation.
+/// If serialization fails, the error is printed instead.
+///
+/// Returned by [`Partial::debug`].
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42 };
+/// let partial = user.with_fields(|u| [u.name]);
+/// let output = format!("{:?}", partial.debug());
+/// assert_eq!(output, format!("{:?}", serde_json::json!({ "name": "John Doe" })));
+/// ```
+pub struct PartialDebug<'p, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    partial: &'p Partial<'a, T, F>,
+}
+
+impl<'a, T, F> Clone for PartialDebug<'_, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'a, T, F> Copy for PartialDebug<'_, 'a, T, F> where T: ?Sized + SerializePartial<'a> {}
+
+impl<'a, T, F> fmt::Debug for PartialDebug<'_, 'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_value(self.partial) {
+            Ok(value) => fmt::Debug::fmt(&value, f),
+            Err(err) => write!(f, "<{}>", err),
+        }
+    }
+}
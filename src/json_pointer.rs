@@ -0,0 +1,157 @@
+//! JSON Pointer based field selection
+//!
+//! Pointers follow [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901).
+//! Single segment pointers such as `/name` select top-level fields, while longer pointers such as `/address/city`
+//! select the fields of nested values, which requires the field to be marked with `#[serde_partial(nested)]`.
+//! The empty pointer selects every field.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{json_pointer::from_json_pointers, Partial, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct Address {
+//!     city: &'static str,
+//!     country: &'static str,
+//! }
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct User {
+//!     name: &'static str,
+//!     #[serde(rename = "a/b")]
+//!     slashed: u8,
+//!     #[serde_partial(nested)]
+//!     address: Address,
+//! }
+//!
+//! let user = User {
+//!     name: "John Doe",
+//!     slashed: 1,
+//!     address: Address { city: "Montreal", country: "Canada" },
+//! };
+//!
+//! let filter = from_json_pointers::<User>(&["/name", "/a~1b", "/address/city"]).unwrap();
+//! let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+//! assert_eq!(
+//!     json,
+//!     serde_json::json!({ "name": "John Doe", "a/b": 1, "address": { "city": "Montreal" } })
+//! );
+//!
+//! let err = from_json_pointers::<User>(&["/name", "/age"]).unwrap_err();
+//! assert_eq!(err.pointer(), "/age");
+//! assert_eq!(err.to_string(), "unknown field referenced by JSON pointer `/age`");
+//!
+//! let err = from_json_pointers::<User>(&["name"]).unwrap_err();
+//! assert_eq!(err.to_string(), "invalid JSON pointer `name`");
+//! ```
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::{filter::SelectFilter, SerializePartial};
+
+/// Error returned when a JSON pointer is invalid or doesn't reference a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JsonPointerError<'a> {
+    pointer: &'a str,
+    kind: JsonPointerErrorKind,
+}
+
+/// Reason why a JSON pointer was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonPointerErrorKind {
+    /// The pointer isn't empty and doesn't start with `/`, or contains an invalid `~` escape.
+    Invalid,
+    /// The pointer doesn't reference a serializable field.
+    UnknownField,
+}
+
+impl<'a> JsonPointerError<'a> {
+    /// Returns the pointer which caused the error.
+    pub const fn pointer(&self) -> &'a str {
+        self.pointer
+    }
+
+    /// Returns the reason why the pointer was rejected.
+    pub const fn kind(&self) -> JsonPointerErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for JsonPointerError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            JsonPointerErrorKind::Invalid => write!(f, "invalid JSON pointer `{}`", self.pointer),
+            JsonPointerErrorKind::UnknownField => write!(
+                f,
+                "unknown field referenced by JSON pointer `{}`",
+                self.pointer
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonPointerError<'_> {}
+
+/// Creates a filter for `T` which selects the fields referenced by the provided JSON pointers.
+pub fn from_json_pointers<'p, 'a, T>(
+    pointers: &[&'p str],
+) -> Result<T::Filter, JsonPointerError<'p>>
+where
+    T: ?Sized + SerializePartial<'a>,
+    T::Filter: SelectFilter,
+{
+    let mut filter = T::Filter::default();
+
+    for &pointer in pointers {
+        let error = |kind| JsonPointerError { pointer, kind };
+
+        if pointer.is_empty() {
+            for name in T::field_names() {
+                filter
+                    .select(name)
+                    .map_err(|_| error(JsonPointerErrorKind::UnknownField))?;
+            }
+            continue;
+        }
+
+        let path = match pointer.strip_prefix('/') {
+            Some(path) => path,
+            None => return Err(error(JsonPointerErrorKind::Invalid)),
+        };
+        let mut name = String::with_capacity(path.len());
+        for (i, segment) in path.split('/').enumerate() {
+            if i > 0 {
+                name.push('.');
+            }
+            if !unescape(segment, &mut name) {
+                return Err(error(JsonPointerErrorKind::Invalid));
+            }
+        }
+
+        filter
+            .select(&name)
+            .map_err(|_| error(JsonPointerErrorKind::UnknownField))?;
+    }
+
+    Ok(filter)
+}
+
+/// Appends the unescaped pointer segment to `out`, returning `false` if it contains an invalid escape.
+fn unescape(segment: &str, out: &mut String) -> bool {
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => out.push('~'),
+                Some('1') => out.push('/'),
+                _ => return false,
+            },
+            c => out.push(c),
+        }
+    }
+    true
+}
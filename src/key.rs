@@ -0,0 +1,89 @@
+//! Partial serialization of maps with keys of any type
+//!
+//! [`SerializePartial`][crate::SerializePartial] is only implemented for maps with string keys, since fields are identified by name.
+//! [`SerializeKeyPartial`] is implemented for maps with keys of any type which can be compared for equality,
+//! such as integers, and stores the selected keys directly.
+//!
+//! ## Example
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! use serde_partial::key::SerializeKeyPartial;
+//!
+//! let map = BTreeMap::from([(1, "a"), (2, "b"), (3, "c")]);
+//!
+//! let filtered = map.with_key_fields(|_| [1, 3]);
+//! let json = serde_json::to_value(&filtered).unwrap();
+//! assert_eq!(json, serde_json::json!({ "1": "a", "3": "c" }));
+//!
+//! let filtered = map.with_key_fields(|keys| keys.filter(|k| **k > 1).copied());
+//! let json = serde_json::to_value(&filtered).unwrap();
+//! assert_eq!(json, serde_json::json!({ "2": "b", "3": "c" }));
+//! ```
+
+use serde::ser::{Serialize, Serializer};
+
+/// Trait implemented by maps whose entries can be filtered by key.
+pub trait SerializeKeyPartial<'a>: Serialize {
+    /// Type of the keys.
+    type Key: 'a;
+    /// Iterator over the keys of the map.
+    type Keys: Iterator<Item = &'a Self::Key>;
+    /// Type storing the selected keys.
+    type KeyFilter: 'a;
+
+    /// Returns a value which only serializes the entries with the selected keys.
+    ///
+    /// The `select` closure receives an iterator over the keys of the map and returns the keys to serialize.
+    fn with_key_fields<F, I>(&'a self, select: F) -> KeyPartial<'a, Self>
+    where
+        F: FnOnce(Self::Keys) -> I,
+        I: IntoIterator<Item = Self::Key>;
+
+    /// Serializes the entries whose keys are selected by the filter.
+    fn serialize_key_partial<S>(
+        &self,
+        filter: &Self::KeyFilter,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+}
+
+/// A type which implements [`Serialize`] by only serializing the entries of the map it references whose keys are selected.
+#[derive(Debug)]
+pub struct KeyPartial<'a, M>
+where
+    M: ?Sized + SerializeKeyPartial<'a>,
+{
+    /// The map to serialize.
+    pub value: &'a M,
+    /// The selected keys.
+    pub filter: M::KeyFilter,
+}
+
+impl<'a, M> Clone for KeyPartial<'a, M>
+where
+    M: ?Sized + SerializeKeyPartial<'a>,
+    M::KeyFilter: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<'a, M> Serialize for KeyPartial<'a, M>
+where
+    M: ?Sized + SerializeKeyPartial<'a>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize_key_partial(&self.filter, serializer)
+    }
+}
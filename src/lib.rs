@@ -2,102 +2,1803 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs, missing_debug_implementations, rust_2018_idioms)]
 
-#[cfg(feature = "alloc")]
+#[cfg(any(feature = "alloc", feature = "serde_json"))]
 extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
-use core::{cmp, fmt, hash, marker::PhantomData};
+use core::{borrow::Borrow, cmp, fmt, hash, marker::PhantomData, ops, ptr};
 
 use serde::ser::{Serialize, Serializer};
 
 #[cfg(feature = "alloc")]
 #[cfg_attr(feature = "alloc", path = "alloc.rs")]
 mod feature_alloc;
+#[cfg(feature = "heapless")]
+#[cfg_attr(feature = "heapless", path = "heapless.rs")]
+mod feature_heapless;
+#[cfg(feature = "indexmap")]
+#[cfg_attr(feature = "indexmap", path = "indexmap.rs")]
+mod feature_indexmap;
 #[cfg(feature = "std")]
 #[cfg_attr(feature = "std", path = "std.rs")]
 mod feature_std;
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+mod field_mask;
+#[path = "as_map.rs"]
+mod serde_as_map;
 #[path = "map.rs"]
 mod serde_map;
+#[path = "seq.rs"]
+mod serde_seq;
 #[path = "struct.rs"]
 mod serde_struct;
 
+#[cfg(feature = "actix-web")]
+pub mod actix_web;
+pub mod assert_impls;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "alloc")]
+pub mod codec;
+#[cfg(feature = "elasticsearch")]
+pub mod elasticsearch;
+#[cfg(feature = "erased-serde")]
+pub mod entries;
 pub mod filter;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "serde_json")]
+pub mod json;
+#[cfg(feature = "alloc")]
+pub mod json_pointer;
+#[cfg(feature = "alloc")]
+pub mod key;
+pub mod macro_helpers;
+#[cfg(feature = "merge-patch")]
+pub mod merge_patch;
+pub mod model;
+#[cfg(feature = "mongodb")]
+pub mod mongodb;
+#[cfg(feature = "pattern-filter")]
+pub mod pattern;
+#[cfg(feature = "regex")]
+pub mod regex;
+#[cfg(feature = "schemars")]
+pub mod schema;
+#[cfg(feature = "sql")]
+pub mod sql;
 pub use filter::SerializeFilter;
+pub use model::IntoPartial;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "erased-serde")]
+    pub use erased_serde_crate as erased_serde;
+    pub use serde;
+}
 
 /// Derive macro for the [`SerializePartial`] trait.
+///
+/// Generic structs are supported, with their lifetime, type and const parameters forwarded to the generated types.
+///
+/// The generated [`Fields`][SerializePartial::Fields] and [`Filter`][SerializePartial::Filter] types are named `{Struct}Fields` and `{Struct}Filter`.
+/// They are defined in an anonymous scope so they never clash with other items in the same module,
+/// but their names could shadow types referenced in the bounds of a generic struct.
+/// The `#[serde_partial(fields_name = "...", filter_name = "...")]` container attribute can be used to rename them in this case.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// trait WrapperFields: Serialize {}
+/// impl WrapperFields for u8 {}
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde_partial(fields_name = "WrapperFieldList")]
+/// struct Wrapper<'l, T: WrapperFields> {
+///     inner: T,
+///     label: &'l str,
+/// }
+///
+/// let label = String::from("answer");
+/// let wrapper = Wrapper { inner: 42u8, label: &label };
+/// let filtered = wrapper.with_fields(|w| [w.inner]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "inner": 42 }));
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Page<T, const N: usize>
+/// where
+///     T: Serialize,
+/// {
+///     items: Vec<T>,
+///     next: Option<usize>,
+/// }
+///
+/// let page = Page::<_, 10> { items: vec!["a", "b"], next: Some(2) };
+/// let filtered = page.with_fields(|p| [p.items]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "items": ["a", "b"] }));
+/// ```
+///
+/// ## Accessor functions
+///
+/// The `#[serde_partial(generate_accessor_fn)]` container attribute generates a `{field}_field` associated function
+/// for each field, which returns the same [`Field`] as the [`Fields`][SerializePartial::Fields] type without having to name it.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde_partial(generate_accessor_fn)]
+/// struct User {
+///     #[serde(rename = "fullName")]
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// assert_eq!(User::name_field().name(), "fullName");
+///
+/// let user = User { name: "John Doe", age: 42 };
+/// let json = serde_json::to_value(&user.with_fields(|_| [User::age_field()])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "age": 42 }));
+/// ```
+///
+/// ## Aliases
+///
+/// The `#[serde_partial(alias = "...")]` field attribute adds a member named after the alias to the [`Fields`][SerializePartial::Fields],
+/// whose [`Field`] selects the same field as the original one, including when selecting by name.
+/// This is useful to keep accepting the former name of a renamed field.
+/// The field is still serialized under its serde name, and only once when both names are selected.
+/// Aliases aren't listed in the `FIELD_NAMES` and aren't yielded when iterating over the fields.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     #[serde_partial(alias = "userName")]
+///     full_name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { full_name: "John Doe", age: 42 };
+///
+/// let json = serde_json::to_value(user.with_fields(|u| [u.userName])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe" }));
+///
+/// let json = serde_json::to_value(user.with_fields(|u| [u.full_name, u.userName])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe" }));
+///
+/// let filter = <User as SerializePartial>::Filter::from_names(["userName", "age"]).unwrap();
+/// assert_eq!(filter, <User as SerializePartial>::Filter::from_names(["fullName", "age"]).unwrap());
+/// ```
+///
+/// ## Filter builders
+///
+/// The `#[serde_partial(builder)]` container attribute generates a `{Filter}Builder` type, returned by the `builder` associated function of the [`Filter`][SerializePartial::Filter].
+/// It has a setter named after each field, taking whether the field is selected, and a `build` method returning the filter.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// #[serde_partial(builder)]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// type UserFilter = <User as SerializePartial<'static>>::Filter;
+///
+/// let filter = UserFilter::builder().full_name(true).age(true).build();
+/// assert_eq!(filter, UserFilter::from_names(["fullName", "age"]).unwrap());
+///
+/// let user = User { full_name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let json = serde_json::to_value(Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe", "age": 42 }));
+/// ```
+///
+/// ## Serializable fields
+///
+/// The `#[serde_partial(serde_fields)]` container attribute implements [`Serialize`] for the [`Fields`][SerializePartial::Fields] type
+/// and [`Deserialize`][serde::Deserialize] for the [`Filter`][SerializePartial::Filter] type.
+/// This makes it possible to send the available fields to clients and to receive their selection.
+/// Human readable formats serialize the fields as a map from Rust names to serde names, and other formats as a sequence of serde names.
+/// Filters are deserialized from a sequence of serde names, see [`filter::deserialize_selection`].
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// #[serde_partial(serde_fields)]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+/// }
+///
+/// let fields = <User as SerializePartial>::Fields::FIELDS;
+/// let json = serde_json::to_value(&fields).unwrap();
+/// assert_eq!(json, serde_json::json!({ "full_name": "fullName", "age": "age" }));
+///
+/// let filter: <User as SerializePartial>::Filter = serde_json::from_str(r#"["fullName"]"#).unwrap();
+/// let user = User { full_name: "John Doe", age: 42 };
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "fullName": "John Doe" }));
+/// ```
+///
+/// ## Serde attributes
+///
+/// Field names always match the ones serde uses, and fields which serde never serializes aren't selectable.
+///
+/// - `rename` and `rename_all` change the name of the [`Field`]s
+/// - `skip` and `skip_serializing` remove the field from the [`Fields`][SerializePartial::Fields]
+/// - `skip_serializing_if` still skips the field when the predicate matches, even if it is selected
+/// - `with` and `serialize_with` are used to serialize the value of selected fields
+///
+/// Fields using `#[serde(flatten)]` are supported as long as their type derives [`SerializePartial`], see below.
+///
+/// ```
+/// use serde::{Serialize, Serializer};
+/// use serde_partial::SerializePartial;
+///
+/// fn uppercase<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+///     serializer.serialize_str(&value.to_uppercase())
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     #[serde(rename = "years")]
+///     age: u8,
+///     #[serde(skip_serializing)]
+///     password_hash: &'static str,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     nickname: Option<&'static str>,
+///     #[serde(serialize_with = "uppercase")]
+///     country_code: &'static str,
+/// }
+///
+/// let user = User {
+///     full_name: "John Doe",
+///     age: 42,
+///     password_hash: "...",
+///     nickname: None,
+///     country_code: "ca",
+/// };
+///
+/// assert_eq!(User::field_names(), ["fullName", "years", "nickname", "countryCode"]);
+///
+/// // the names are also available at compile time
+/// const FIELD_NAMES: &[&str] = <User as SerializePartial>::Fields::FIELD_NAMES;
+/// assert_eq!(FIELD_NAMES, User::field_names());
+///
+/// let filtered = user.with_fields(|u| [u.age, u.nickname, u.country_code]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "years": 42, "countryCode": "CA" }));
+/// ```
+///
+/// ## Flattened fields
+///
+/// The entries of fields using `#[serde(flatten)]` are serialized as part of the parent struct, so they are selected individually.
+/// The [`Fields`][SerializePartial::Fields] of the parent have a member holding the [`Fields`][SerializePartial::Fields] of the flattened type,
+/// whose fields are [cast][Field::cast] to fields of the parent when selected.
+/// Flattened fields aren't listed in the `FIELD_NAMES` of the parent and aren't yielded when iterating over its fields,
+/// and the filtered length is never known since serde doesn't provide a length for structs with flattened fields.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Timestamps {
+///     created_at: u64,
+///     updated_at: u64,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Post {
+///     title: &'static str,
+///     #[serde(flatten)]
+///     timestamps: Timestamps,
+/// }
+///
+/// let post = Post { title: "Hello", timestamps: Timestamps { created_at: 1, updated_at: 2 } };
+///
+/// let filtered = post.with_fields(|p| [p.title, p.timestamps.updated_at.cast()]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "title": "Hello", "updated_at": 2 }));
+///
+/// let filtered = post.without_fields(|p| [p.title]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "created_at": 1, "updated_at": 2 }));
+/// ```
+///
+/// ## Enums
+///
+/// Untagged enums are supported, with the fields of all their struct variants being selectable by name regardless of the variant.
+/// Field names follow the rules of their variant, including `#[serde(rename_all = "...")]` on variants and `#[serde(rename_all_fields = "...")]` on the enum.
+/// Fields with the same identifier in different variants are considered the same field, and must therefore be serialized with the same name.
+/// Newtype variants aren't supported.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(untagged, rename_all_fields = "camelCase")]
+/// enum Account {
+///     Person {
+///         first_name: &'static str,
+///         last_name: &'static str,
+///     },
+///     #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+///     Company {
+///         legal_name: &'static str,
+///         tax_id: u32,
+///     },
+///     Anonymous,
+/// }
+///
+/// assert_eq!(
+///     Account::field_names(),
+///     ["firstName", "lastName", "LEGAL_NAME", "TAX_ID"]
+/// );
+///
+/// let person = Account::Person { first_name: "John", last_name: "Doe" };
+/// let json = serde_json::to_value(&person.with_fields(|a| [a.first_name, a.legal_name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "firstName": "John" }));
+///
+/// let company = Account::Company { legal_name: "Doe Inc.", tax_id: 42 };
+/// let json = serde_json::to_value(&company.with_fields(|a| [a.first_name, a.legal_name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "LEGAL_NAME": "Doe Inc." }));
+///
+/// let json = serde_json::to_value(&Account::Anonymous.with_fields(|a| [a.tax_id])).unwrap();
+/// assert_eq!(json, serde_json::Value::Null);
+/// ```
+///
+/// Internally tagged enums, using `#[serde(tag = "...")]`, are also supported.
+/// Their tag is an additional field which is always serialized, named after the tag if it's a valid identifier and `tag` otherwise.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(tag = "type")]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Rectangle { width: f64, height: f64 },
+/// }
+///
+/// assert_eq!(Shape::field_names(), ["type", "radius", "width", "height"]);
+/// assert_eq!(<Shape as SerializePartial>::ALWAYS_FIELDS, ["type"]);
+///
+/// let rectangle = Shape::Rectangle { width: 2.0, height: 1.0 };
+/// let json = serde_json::to_value(&rectangle.with_fields(|s| [s.radius, s.width])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "type": "Rectangle", "width": 2.0 }));
+///
+/// let json = serde_json::to_value(&rectangle.without_fields(|s| [s.r#type, s.width])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "type": "Rectangle", "height": 1.0 }));
+/// ```
+///
+/// ## Newtype structs
+///
+/// Newtype structs forward their implementation to the wrapped type, which must implement [`SerializePartial`].
+/// Their [`Fields`][SerializePartial::Fields] are the ones of the wrapped type, which can be converted using [`Field::cast`].
+/// Tuple structs with more than one field and unit structs aren't supported.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Field, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Admin(User);
+///
+/// let admin = Admin(User { name: "John Doe", age: 42 });
+/// let filtered = admin.with_fields(|u| [u.name.cast()]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+///
+/// // newtype structs can wrap other newtype structs
+/// #[derive(Serialize, SerializePartial)]
+/// struct SuperAdmin(Admin);
+///
+/// let super_admin = SuperAdmin(admin);
+/// let filtered = super_admin.without_fields(|u| [u.name.cast()]);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "age": 42 }));
+/// ```
+///
+/// ## Nested selection
+///
+/// Fields marked with `#[serde_partial(nested)]` can have the fields of their own value selected using dotted paths such as `"customer.name"`.
+/// When a field contains several values of the nested type, like a `Vec` or an `Option`, the type should be specified with `#[serde_partial(nested = "Type")]`.
+/// Nested types must implement [`SerializePartial`] using the derive macro and can't borrow or be generic.
+///
+/// Selecting a path also selects the field containing it, and only the selected paths of its value are serialized.
+/// Paths can be passed to the generated `Filter::from_names` and `Filter::select` methods, or to [`with_fields`][SerializePartial::with_fields] using [`Field::new`].
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Field, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Customer {
+///     name: &'static str,
+///     email: &'static str,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Item {
+///     sku: u32,
+///     price: u32,
+/// }
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Order {
+///     id: u32,
+///     #[serde_partial(nested)]
+///     customer: Customer,
+///     #[serde_partial(nested = "Item")]
+///     items: Vec<Item>,
+/// }
+///
+/// let order = Order {
+///     id: 1,
+///     customer: Customer { name: "John Doe", email: "john.doe@example.com" },
+///     items: vec![Item { sku: 7, price: 100 }, Item { sku: 8, price: 250 }],
+/// };
+///
+/// let filtered = order.with_fields(|o| {
+///     [o.id, Field::new("customer.name"), Field::new("items.sku")]
+/// });
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(
+///     json,
+///     serde_json::json!({
+///         "id": 1,
+///         "customer": { "name": "John Doe" },
+///         "items": [{ "sku": 7 }, { "sku": 8 }],
+///     })
+/// );
+///
+/// type OrderFilter = <Order as SerializePartial<'static>>::Filter;
+/// assert!(OrderFilter::from_names(["customer.phone"]).is_err());
+/// ```
+///
+/// ## Always and never serialized fields
+///
+/// Fields marked with `#[serde_partial(always)]` are serialized regardless of the filter, which is useful for identifiers.
+/// Fields marked with `#[serde_partial(never)]` are excluded from the selectable fields and never serialized by partials,
+/// similarly to `#[serde(skip)]` but without affecting the regular [`Serialize`] implementation.
+/// Both attributes can be used alongside `#[serde(...)]` attributes.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct Resource {
+///     #[serde_partial(always)]
+///     #[serde(rename = "ID")]
+///     id: u32,
+///     name: &'static str,
+///     size: u32,
+///     #[serde_partial(never)]
+///     secret: &'static str,
+/// }
+///
+/// let resource = Resource { id: 1, name: "file", size: 42, secret: "hunter2" };
+///
+/// let json = serde_json::to_value(&resource.with_fields(|r| [r.name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "ID": 1, "name": "file" }));
+///
+/// let json = serde_json::to_value(&resource.without_fields(|r| [r.id, r.name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "ID": 1, "size": 42 }));
+///
+/// assert_eq!(<Resource as SerializePartial>::FIELD_COUNT, Some(3));
+/// type ResourceFilter = <Resource as SerializePartial<'static>>::Filter;
+/// assert!(ResourceFilter::from_names(["secret"]).is_err());
+///
+/// // the regular implementation is unaffected
+/// let json = serde_json::to_value(&resource).unwrap();
+/// assert_eq!(json["secret"], "hunter2");
+/// ```
+///
+/// ## Field groups
+///
+/// Fields can be tagged with one or more `#[serde_partial(group = "...")]` attributes.
+/// A method named after each group is generated on the [`Fields`][SerializePartial::Fields] type, returning an array of the fields in the group.
+/// Group names must be valid identifiers, and a field can belong to any number of groups but only once to each.
+/// Groups are useful to define presets of fields which are often selected together.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     #[serde_partial(group = "summary", group = "public")]
+///     name: &'static str,
+///     #[serde_partial(group = "public")]
+///     bio: &'static str,
+///     #[serde_partial(group = "summary")]
+///     email: &'static str,
+///     password_hash: &'static str,
+/// }
+///
+/// let user = User {
+///     name: "John Doe",
+///     bio: "Just a regular person",
+///     email: "john.doe@example.com",
+///     password_hash: "...",
+/// };
+///
+/// let json = serde_json::to_value(&user.with_fields(|u| u.summary())).unwrap();
+/// assert_eq!(
+///     json,
+///     serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com" })
+/// );
+///
+/// let json = serde_json::to_value(&user.with_fields(|u| u.public())).unwrap();
+/// assert_eq!(
+///     json,
+///     serde_json::json!({ "name": "John Doe", "bio": "Just a regular person" })
+/// );
+/// ```
+///
+/// ## Permissions
+///
+/// Fields can be marked with one or more `#[serde_partial(requires = "...")]` attributes naming the permissions needed to serialize them.
+/// The generated [`Filter`][SerializePartial::Filter] then has an associated `u64` constant with a distinct bit for each permission,
+/// and a `for_permissions` constructor selecting the fields whose required permissions are all set in a mask.
+/// Fields without requirements are always selected, and at most 64 permissions are supported.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{Partial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     #[serde_partial(requires = "STAFF")]
+///     email: &'static str,
+///     #[serde_partial(requires = "STAFF", requires = "ADMIN")]
+///     ip: &'static str,
+/// }
+///
+/// type Filter = <User as SerializePartial<'static>>::Filter;
+///
+/// let user = User { name: "John Doe", email: "john.doe@example.com", ip: "127.0.0.1" };
+///
+/// let filter = Filter::for_permissions(0);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+///
+/// let filter = Filter::for_permissions(Filter::STAFF);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com" }));
+///
+/// let filter = Filter::for_permissions(Filter::STAFF | Filter::ADMIN);
+/// let json = serde_json::to_value(&Partial { value: &user, filter }).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "email": "john.doe@example.com", "ip": "127.0.0.1" }));
+/// ```
+///
+/// ## API versions
+///
+/// Fields can be marked with `#[serde_partial(since = N)]` and `#[serde_partial(removed_in = N)]` to record the API versions exposing them.
+/// When any field uses these attributes, a `versions` method returning a [`VersionFilter`][filter::VersionFilter] is generated on the [`Fields`][SerializePartial::Fields] type.
+/// This requires the `alloc` feature.
+///
+/// ## Length hints
+///
+/// The generated filter reports how many fields will be serialized, which is used as the length hint passed to the serializer and returned by [`Partial::len`].
+/// Fields using `#[serde(skip_serializing_if = "...")]` can be skipped depending on their value, so the count can't be known ahead of time
+/// and the filter of a struct with such fields always reports an unknown length.
+///
+/// Since the generated filter's length is exact, the remaining fields are skipped without consulting the filter once all the selected ones have been serialized.
+/// See [`SerializeFilter::exact_len`].
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     nickname: Option<&'static str>,
+/// }
+///
+/// let user = User { name: "John Doe", nickname: None };
+/// let filtered = user.with_fields(|u| [u.name, u.nickname]);
+/// assert_eq!(filtered.len(), None);
+/// let json = serde_json::to_value(&filtered).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+/// ```
+///
+/// Formats which prefix structs with their number of fields, such as CBOR, rely on this hint to produce valid output.
+/// Such formats should only be used with filters which report a known length.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+/// use serde_test::{Configure, Token};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let filtered = user.with_fields(|u| [u.name, u.age]);
+/// assert_eq!(filtered.len(), Some(2));
+///
+/// serde_test::assert_ser_tokens(
+///     &(&filtered).compact(),
+///     &[
+///         Token::Struct { name: "User", len: 2 },
+///         Token::Str("name"),
+///         Token::Str("John Doe"),
+///         Token::Str("age"),
+///         Token::U8(42),
+///         Token::StructEnd,
+///     ],
+/// );
+/// ```
 pub use serde_partial_macro::SerializePartial;
 
+/// Defines a function which serializes only some fields of a value, for use with `#[serde(serialize_with = "...")]`.
+///
+/// Serde expects the attribute to name a function, so the macro defines one with the provided name,
+/// which selects the listed fields before serializing the value.
+/// This makes it possible to partially serialize a field of a type which only derives [`Serialize`].
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     email: &'static str,
+/// }
+///
+/// serde_partial::only!(fn user_name(User) { name });
+///
+/// #[derive(Serialize)]
+/// struct Post {
+///     title: &'static str,
+///     #[serde(serialize_with = "user_name")]
+///     author: User,
+/// }
+///
+/// let post = Post {
+///     title: "Hello",
+///     author: User { name: "John Doe", email: "john.doe@example.com" },
+/// };
+/// let json = serde_json::to_value(&post).unwrap();
+/// assert_eq!(json, serde_json::json!({ "title": "Hello", "author": { "name": "John Doe" } }));
+/// ```
+#[macro_export]
+macro_rules! only {
+    ($vis:vis fn $name:ident($ty:ty) { $($field:ident),* $(,)? }) => {
+        $vis fn $name<__S>(value: &$ty, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+        where
+            __S: $crate::__private::serde::Serializer,
+        {
+            $crate::__private::serde::Serialize::serialize(
+                &$crate::SerializePartial::with_fields(value, |fields| [$(fields.$field),*]),
+                serializer,
+            )
+        }
+    };
+}
+
 /// Trait implemented by types which can be partially serialized.
+///
+/// References to types implementing this trait also implement it, selecting the same fields as the referenced type.
+/// Their [`Fields`][SerializePartial::Fields] are the ones of the referenced type, which should be [cast][Field::cast] when selected.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// fn names_only<'a, T>(value: &'a T) -> serde_json::Value
+/// where
+///     T: SerializePartial<'a>,
+/// {
+///     serde_json::to_value(value.with_fields(|_| [serde_partial::Field::new("name")])).unwrap()
+/// }
+///
+/// let user = User { name: "John Doe", age: 42 };
+/// assert_eq!(names_only(&user), serde_json::json!({ "name": "John Doe" }));
+/// assert_eq!(names_only(&&user), serde_json::json!({ "name": "John Doe" }));
+///
+/// let mut user = user;
+/// let json = serde_json::to_value((&mut user).with_fields(|u| [u.age.cast()])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "age": 42 }));
+/// ```
 pub trait SerializePartial<'a>: Serialize {
     /// Type which provides the list of serializable fields.
     ///
     /// When using the derive macro, this type is a struct with the same fields as the original struct.
     /// It will implement [`IntoIterator`] to make it possible to iterate over the available fields, and [`Copy`] and [`Clone`] for convenience.
-    /// It will also have a `FIELDS: Self` associated constant.
+    /// It will also have a `FIELDS: Self` associated constant, a `FIELD_NAMES: &'static [&'static str]` associated constant and a `FIELD_COUNT: usize` associated constant.
+    /// Finally, it will have `names`, `len`, `is_empty` and `contains` methods, which are useful to validate field names provided at runtime.
+    ///
+    /// Fields marked with `#[serde(skip_serializing)]` aren't part of the serializable fields,
+    /// but their names are listed by the `SKIPPED_NAMES` associated constant and the `skipped_names` associated function.
+    /// This makes it possible to tell apart fields which are never serialized from unknown ones.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// #     #[serde(skip_serializing)]
+    /// #     password: &'static str,
+    /// # }
+    /// #
+    /// type UserFields = <User as SerializePartial<'static>>::Fields;
+    ///
+    /// let fields = UserFields::FIELDS;
+    /// assert_eq!(fields.names().collect::<Vec<_>>(), ["name", "age"]);
+    /// assert_eq!(fields.len(), 2);
+    /// assert!(fields.contains("age"));
+    /// assert!(!fields.contains("email"));
+    /// assert!(!fields.contains("password"));
+    /// assert_eq!(UserFields::skipped_names(), ["password"]);
+    /// ```
     type Fields: 'a;
     /// Type which can be used to check whether a serializable field should be skipped.
+    ///
+    /// When using the derive macro, this type implements [`From`] the [`Fields`][SerializePartial::Fields] to select every field,
+    /// and [`From`] an array of fields to select only those.
+    /// It also has a `from_names` associated function and implements [`TryFrom`][core::convert::TryFrom] a slice of names,
+    /// which both return an [`UnknownFieldError`] for names which don't match any field.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Partial, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// type UserFilter = <User as SerializePartial<'static>>::Filter;
+    ///
+    /// let user = User { name: "John Doe", age: 42 };
+    /// let fields = <User as SerializePartial>::Fields::FIELDS;
+    ///
+    /// let json = serde_json::to_value(Partial { value: &user, filter: UserFilter::from(fields) }).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+    ///
+    /// let json = serde_json::to_value(Partial { value: &user, filter: UserFilter::from([fields.age]) }).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    ///
+    /// let filter = UserFilter::try_from(&["age"][..]).unwrap();
+    /// assert_eq!(filter, UserFilter::from([fields.age]));
+    /// assert_eq!(filter, UserFilter::from_names(["age"]).unwrap());
+    /// assert_eq!(UserFilter::try_from(&["email"][..]).unwrap_err().name(), "email");
+    /// ```
     type Filter: SerializeFilter<Self> + 'a;
 
-    /// Returns a value which forwards the [`Serialize`] implementation but only serializes the selected fields.
+    /// Number of serializable fields, if known at compile time.
+    ///
+    /// When using the derive macro, this is the same as the `FIELD_COUNT` associated constant of [`Fields`][SerializePartial::Fields].
+    /// It is [`None`] for types with fields only known at runtime such as maps.
+    const FIELD_COUNT: Option<usize> = None;
+
+    /// Names of the fields which are serialized regardless of the filter.
+    ///
+    /// When using the derive macro, these are the fields marked with `#[serde_partial(always)]`.
+    const ALWAYS_FIELDS: &'static [&'static str] = &[];
+
+    /// Names of the fields which are never serialized by partials, regardless of the filter.
+    ///
+    /// When using the derive macro, these are the fields marked with `#[serde_partial(never)]`.
+    /// They aren't part of the [`Fields`][SerializePartial::Fields] and aren't counted in [`FIELD_COUNT`][SerializePartial::FIELD_COUNT].
+    const NEVER_FIELDS: &'static [&'static str] = &[];
+
+    /// Returns a value which forwards the [`Serialize`] implementation but only serializes the selected fields.
+    ///
+    /// The `select` closure receives an instance of [`Fields`][SerializePartial::Fields] which can than be used to select which fields should be serialized.
+    /// The closure can return any type which implements [`IntoIterator`]. This could be an array, but could also be a `Vec` or an [`Iterator`] with fields selected at runtime.
+    ///
+    /// ## Panics
+    ///
+    /// When using the derive macro, panics if one of the selected fields doesn't exist, with a message including both the field name and the type name.
+    /// Use [`try_with_fields`][SerializePartial::try_with_fields] when selecting fields created at runtime with [`Field::new`].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_partial::SerializePartial;
+    ///
+    /// #[derive(Serialize, SerializePartial)]
+    /// #[serde(rename_all = "camelCase")]
+    /// struct User {
+    ///     full_name: &'static str,
+    ///     age: u8,
+    ///     #[serde(rename = "contact")]
+    ///     email: &'static str,
+    /// }
+    ///
+    /// const USER: User = User {
+    ///     full_name: "John Doe",
+    ///     age: 42,
+    ///     email: "john.doe@example.com",
+    /// };
+    ///
+    /// // serialize only the `full_name` field
+    /// let filtered = USER.with_fields(|u| [u.full_name]);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     serde_json::json!({
+    ///         "fullName": USER.full_name,
+    ///     })
+    /// );
+    ///
+    /// // the field list doesn't have to be an array
+    /// // serialize every field with a name longer than 4 characters
+    /// let filtered = USER.with_fields(|u| u.into_iter().filter(|f| f.name().len() > 4));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     serde_json::json!({
+    ///         "fullName": USER.full_name,
+    ///         "contact": USER.email,
+    ///     })
+    /// );
+    ///
+    /// // field names respect serde attributes
+    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.full_name.name(), "fullName");
+    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.age.name(), "age");
+    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.email.name(), "contact");
+    /// ```
+    fn with_fields<F, I>(&'a self, select: F) -> Partial<'a, Self>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>;
+
+    /// Same as [`with_fields`][SerializePartial::with_fields] but returns an error instead of panicking if a selected field doesn't exist.
+    ///
+    /// This is useful when the fields are selected at runtime, for example from a query string.
+    /// The default implementation forwards to [`with_fields`][SerializePartial::with_fields] and never fails,
+    /// which is appropriate for types with fields only known at runtime such as maps.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// # #[derive(Debug, Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42 };
+    /// #
+    /// let filtered = USER.try_with_fields(|_| [Field::new("name")]).unwrap();
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": USER.name }));
+    ///
+    /// let err = USER.try_with_fields(|_| [Field::new("phone")]).unwrap_err();
+    /// assert_eq!(err.name(), "phone");
+    /// ```
+    fn try_with_fields<F, I>(
+        &'a self,
+        select: F,
+    ) -> Result<Partial<'a, Self>, UnknownFieldError<'a>>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>,
+    {
+        Ok(self.with_fields(select))
+    }
+
+    /// Same as [`with_fields`][SerializePartial::with_fields] but takes an already collected list of fields instead of a closure.
+    ///
+    /// This is useful when the fields are computed in one place and applied in another.
+    /// The list can hold the fields or references to them, so vectors, slices and iterator adapters can all be passed as is.
+    /// Unknown and duplicate fields are handled the same way as [`with_fields`][SerializePartial::with_fields].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// let user = User { name: "John Doe", age: 42 };
+    /// let fields: Vec<Field<User>> = vec![<User as SerializePartial>::Fields::FIELDS.age];
+    ///
+    /// // by reference, from a vector or a slice
+    /// let filtered = user.with_field_list(&fields);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    ///
+    /// let filtered = user.with_field_list(&fields[..]);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    ///
+    /// // from an iterator adapter
+    /// let filtered = user.with_field_list(fields.iter().filter(|f| f.is("age")));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    ///
+    /// // by value
+    /// let filtered = user.with_field_list(fields);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": 42 }));
+    /// ```
+    ///
+    /// Fields collected outside of the closure passed to [`with_fields`][SerializePartial::with_fields] can also be moved into it.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// let user = User { name: "John Doe", age: 42 };
+    /// let fields: Vec<Field<User>> = vec![<User as SerializePartial>::Fields::FIELDS.name];
+    ///
+    /// let filtered = user.with_fields(|u| fields.iter().copied().chain([u.age]));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+    ///
+    /// let filtered = user.with_fields(move |_| fields);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+    /// ```
+    fn with_field_list<I>(&'a self, fields: I) -> Partial<'a, Self>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Field<'a, Self>>,
+    {
+        self.with_fields(|_| fields.into_iter().map(|f| *f.borrow()))
+    }
+
+    /// Same as [`with_fields`][SerializePartial::with_fields] but fields are opt-out instead of opt-in.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # #[serde(rename_all = "camelCase")]
+    /// # struct User {
+    /// #     full_name: &'static str,
+    /// #     age: u8,
+    /// #     #[serde(rename = "contact")]
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// # const USER: User = User {
+    /// #     full_name: "John Doe",
+    /// #     age: 42,
+    /// #     email: "john.doe@example.com",
+    /// # };
+    /// #
+    /// let filtered = USER.without_fields(|u| [u.email]);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     serde_json::json!({
+    ///         "fullName": USER.full_name,
+    ///         "age": USER.age,
+    ///     })
+    /// );
+    /// ```
+    fn without_fields<F, I>(
+        &'a self,
+        select: F,
+    ) -> Partial<'a, Self, filter::InverseFilter<'a, Self>>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>,
+    {
+        let Partial { value, filter } = self.with_fields(select);
+        Partial {
+            value,
+            filter: filter::InverseFilter::new(filter),
+        }
+    }
+
+    /// Same as [`without_fields`][SerializePartial::without_fields] but returns an error instead of panicking if a selected field doesn't exist.
+    ///
+    /// See [`try_with_fields`][SerializePartial::try_with_fields].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// # #[derive(Debug, Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42 };
+    /// #
+    /// let filtered = USER.try_without_fields(|_| [Field::new("name")]).unwrap();
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    ///
+    /// let err = USER.try_without_fields(|_| [Field::new("phone")]).unwrap_err();
+    /// assert_eq!(err.name(), "phone");
+    /// ```
+    fn try_without_fields<F, I>(
+        &'a self,
+        select: F,
+    ) -> Result<Partial<'a, Self, filter::InverseFilter<'a, Self>>, UnknownFieldError<'a>>
+    where
+        F: FnOnce(Self::Fields) -> I,
+        I: IntoIterator<Item = Field<'a, Self>>,
+    {
+        let Partial { value, filter } = self.try_with_fields(select)?;
+        Ok(Partial {
+            value,
+            filter: filter::InverseFilter::new(filter),
+        })
+    }
+
+    /// Same as [`without_fields`][SerializePartial::without_fields] but takes an already computed filter.
+    ///
+    /// This makes it possible to reuse a filter both to include fields, by building a [`Partial`] from it directly,
+    /// and to exclude them.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Partial, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", email: "john.doe@example.com" };
+    /// #
+    /// let sensitive = USER.with_fields(|u| [u.email]).filter;
+    ///
+    /// let admin = Partial { value: &USER, filter: sensitive };
+    /// let json = serde_json::to_value(&admin).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "email": USER.email }));
+    ///
+    /// let public = USER.inverse_partial(sensitive);
+    /// let json = serde_json::to_value(&public).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": USER.name }));
+    /// ```
+    fn inverse_partial(
+        &'a self,
+        filter: Self::Filter,
+    ) -> Partial<'a, Self, filter::InverseFilter<'a, Self>> {
+        Partial {
+            value: self,
+            filter: filter::InverseFilter::new(filter),
+        }
+    }
+
+    /// Same as [`with_fields`][SerializePartial::with_fields] but selects the fields matching a predicate.
+    ///
+    /// The predicate is called while serializing, so the filtered length can't be known ahead of time.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+    /// #
+    /// let filtered = USER.with_fields_if(|f| f.name().len() > 3);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": USER.name, "email": USER.email }));
+    ///
+    /// let filtered = USER.without_fields_if(|f| f.name().len() > 3);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    /// ```
+    fn with_fields_if<P>(
+        &'a self,
+        predicate: P,
+    ) -> Partial<'a, Self, filter::ClosureFilter<'a, Self, P>>
+    where
+        P: Fn(Field<'_, Self>) -> bool,
+    {
+        Partial {
+            value: self,
+            filter: filter::ClosureFilter::new(predicate),
+        }
+    }
+
+    /// Same as [`with_fields_if`][SerializePartial::with_fields_if] but skips the fields matching the predicate instead.
+    fn without_fields_if<P>(
+        &'a self,
+        predicate: P,
+    ) -> Partial<'a, Self, filter::InverseFilter<'a, Self, filter::ClosureFilter<'a, Self, P>>>
+    where
+        P: Fn(Field<'_, Self>) -> bool,
+    {
+        Partial {
+            value: self,
+            filter: filter::InverseFilter::new(filter::ClosureFilter::new(predicate)),
+        }
+    }
+
+    /// Returns the names of all the serializable fields.
+    ///
+    /// When using the derive macro, this returns the `FIELD_NAMES` associated constant of [`Fields`][SerializePartial::Fields].
+    /// The default implementation returns an empty slice, which is appropriate for types with fields only known at runtime such as maps.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # #[serde(rename_all = "camelCase")]
+    /// # struct User {
+    /// #     full_name: &'static str,
+    /// #     age: u8,
+    /// #     #[serde(rename = "contact")]
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// assert_eq!(User::field_names(), ["fullName", "age", "contact"]);
+    /// assert_eq!(<User as SerializePartial>::Fields::FIELD_NAMES, User::field_names());
+    /// assert_eq!(<User as SerializePartial>::Fields::FIELD_COUNT, 3);
+    /// assert_eq!(<User as SerializePartial>::FIELD_COUNT, Some(3));
+    /// ```
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+}
+
+macro_rules! impl_serialize_partial_ref {
+    ($($ref:tt)*) => {
+        /// Forwards to the implementation of the referenced type, whose filter is wrapped in a [`NewtypeFilter`][filter::NewtypeFilter].
+        impl<'a, 'r, T> SerializePartial<'a> for $($ref)* T
+        where
+            T: ?Sized + SerializePartial<'a>,
+            'r: 'a,
+        {
+            type Fields = T::Fields;
+            type Filter = filter::NewtypeFilter<Self, T, T::Filter>;
+
+            const FIELD_COUNT: Option<usize> = T::FIELD_COUNT;
+            const ALWAYS_FIELDS: &'static [&'static str] = T::ALWAYS_FIELDS;
+            const NEVER_FIELDS: &'static [&'static str] = T::NEVER_FIELDS;
+
+            fn with_fields<F, I>(&'a self, select: F) -> Partial<'a, Self>
+            where
+                F: FnOnce(Self::Fields) -> I,
+                I: IntoIterator<Item = Field<'a, Self>>,
+            {
+                let inner = T::with_fields(&**self, |fields| {
+                    select(fields).into_iter().map(Field::cast)
+                });
+                Partial {
+                    value: self,
+                    filter: filter::NewtypeFilter::new(inner.filter),
+                }
+            }
+
+            fn try_with_fields<F, I>(
+                &'a self,
+                select: F,
+            ) -> Result<Partial<'a, Self>, UnknownFieldError<'a>>
+            where
+                F: FnOnce(Self::Fields) -> I,
+                I: IntoIterator<Item = Field<'a, Self>>,
+            {
+                let inner = T::try_with_fields(&**self, |fields| {
+                    select(fields).into_iter().map(Field::cast)
+                })?;
+                Ok(Partial {
+                    value: self,
+                    filter: filter::NewtypeFilter::new(inner.filter),
+                })
+            }
+
+            fn field_names() -> &'static [&'static str] {
+                T::field_names()
+            }
+        }
+    };
+}
+
+impl_serialize_partial_ref!(&'r);
+impl_serialize_partial_ref!(&'r mut);
+
+/// A type which implements [`Serialize`] by forwarding the implementation to the value it references while skipping fields according to its filter.
+#[derive(Debug)]
+pub struct Partial<'a, T, F = <T as SerializePartial<'a>>::Filter>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    /// The value to serialize.
+    pub value: &'a T,
+    /// The field filter to use.
+    pub filter: F,
+}
+
+/// A [`Partial`] which serializes structs as maps.
+///
+/// Returned by [`Partial::as_map`].
+#[derive(Debug, Clone, Copy)]
+pub struct AsMap<P> {
+    partial: P,
+}
+
+impl<P> AsMap<P> {
+    /// Returns the wrapped partial.
+    pub fn into_inner(self) -> P {
+        self.partial
+    }
+}
+
+impl<P> Serialize for AsMap<P>
+where
+    P: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.partial
+            .serialize(serde_as_map::StructAsMap { s: serializer })
+    }
+}
+
+/// A [`Partial`] of a [`HashMap`][std::collections::HashMap] which serializes the retained entries sorted by key.
+///
+/// The iteration order of hash maps is arbitrary, which makes their serialized output nondeterministic.
+/// Sorting requires collecting the retained entries before serializing them,
+/// so maps with a stable order such as [`BTreeMap`][std::collections::BTreeMap] or `IndexMap` should be preferred when possible.
+///
+/// Returned by [`Partial::sorted`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct Sorted<P> {
+    partial: P,
+}
+
+#[cfg(feature = "std")]
+impl<P> Sorted<P> {
+    /// Returns the wrapped partial.
+    pub fn into_inner(self) -> P {
+        self.partial
+    }
+}
+
+/// Error returned when a field name doesn't match any of the serializable fields of a type.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::SerializePartial;
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// type UserFilter = <User as SerializePartial<'static>>::Filter;
+///
+/// let filter = UserFilter::from_args(&["name, age", "email"]).unwrap();
+/// assert_eq!(filter, UserFilter::from_names(["name", "age", "email"]).unwrap());
+///
+/// let err = UserFilter::from_args(&["name,phone"]).unwrap_err();
+/// assert_eq!(err.name(), "phone");
+/// assert_eq!(err.expected(), ["name", "age", "email"]);
+/// assert_eq!(
+///     err.to_string(),
+///     "unknown field `phone`, expected one of `name`, `age`, `email`"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownFieldError<'a> {
+    name: &'a str,
+    expected: &'static [&'static str],
+}
+
+/// Newtype around a field name for the specified type.
+///
+/// Fields are compared, ordered and hashed by their serde name only.
+pub struct Field<'a, T: ?Sized> {
+    name: &'a str,
+    rust_name: Option<&'static str>,
+    type_name: Option<&'static str>,
+    index: Option<usize>,
+    _ty: PhantomData<T>,
+}
+
+impl<'a, T, F> Clone for Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            filter: self.filter.clone(),
+        }
+    }
+}
+impl<'a, T, F> Copy for Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: Copy,
+{
+}
+
+impl<'a, T, F> Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+{
+    /// Transforms the filter while keeping the same value.
+    ///
+    /// This makes it possible to further refine a filter produced elsewhere.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{
+    /// #     filter::{AndFilter, ArrayFilter, EmptyFilter, InverseFilter},
+    /// #     SerializePartial,
+    /// # };
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42 };
+    /// #
+    /// let filtered = USER
+    ///     .with_fields(|u| [u.name])
+    ///     .map_filter(InverseFilter::new);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    ///
+    /// // closures can wrap the filter in combinators or replace it entirely
+    /// let fields = <User as SerializePartial>::Fields::FIELDS;
+    /// let filtered = USER
+    ///     .with_fields(|u| [u.name, u.age])
+    ///     .map_filter(|f| AndFilter::new(f, ArrayFilter::new([fields.age])));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    ///
+    /// let filtered = USER.with_fields(|u| [u.name]).map_filter(|_| EmptyFilter);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({}));
+    /// ```
+    pub fn map_filter<G, H>(self, f: G) -> Partial<'a, T, H>
+    where
+        G: FnOnce(F) -> H,
+        H: SerializeFilter<T>,
+    {
+        Partial {
+            value: self.value,
+            filter: f(self.filter),
+        }
+    }
+
+    /// Further restricts the serialized fields to the ones also selected by another filter.
+    ///
+    /// Chaining tightens the set of serialized fields, which is useful when several layers each narrow down what can be serialized.
+    /// Use [`chain_or`][Partial::chain_or] to broaden the set instead.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+    /// #
+    /// let requested = USER.with_fields(|u| [u.age, u.email]).filter;
+    /// let filtered = USER.with_fields(|u| [u.name, u.age]).chain(requested);
+    /// assert_eq!(filtered.len(), Some(1));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    ///
+    /// let requested = USER.with_fields(|u| [u.age, u.email]).filter;
+    /// let filtered = USER.with_fields(|u| [u.name, u.age]).chain_or(requested);
+    /// assert_eq!(filtered.len(), Some(3));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     serde_json::json!({ "name": USER.name, "age": USER.age, "email": USER.email })
+    /// );
+    /// ```
+    pub fn chain<G>(self, filter: G) -> Partial<'a, T, filter::AndFilter<T, F, G>>
+    where
+        F: SerializeFilter<T>,
+        G: SerializeFilter<T>,
+    {
+        Partial {
+            value: self.value,
+            filter: filter::AndFilter::new(self.filter, filter),
+        }
+    }
+
+    /// Extends the serialized fields with the ones selected by another filter.
+    ///
+    /// See [`chain`][Partial::chain].
+    pub fn chain_or<G>(self, filter: G) -> Partial<'a, T, filter::OrFilter<T, F, G>>
+    where
+        F: SerializeFilter<T>,
+        G: SerializeFilter<T>,
+    {
+        Partial {
+            value: self.value,
+            filter: filter::OrFilter::new(self.filter, filter),
+        }
+    }
+
+    /// Uses another filter instead if the current one doesn't select any field.
+    ///
+    /// See [`FallbackFilter`][filter::FallbackFilter].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{filter::IdentityFilter, SerializePartial};
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// # const USER: User = User { name: "John Doe", age: 42 };
+    /// #
+    /// let requested: Vec<&str> = vec![];
+    /// let filtered = USER
+    ///     .try_with_fields(|_| requested.iter().copied().map(serde_partial::Field::new))
+    ///     .unwrap()
+    ///     .or_fallback(IdentityFilter);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "name": USER.name, "age": USER.age }));
+    ///
+    /// let filtered = USER.with_fields(|u| [u.age]).or_fallback(IdentityFilter);
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age }));
+    /// ```
+    pub fn or_fallback<G>(self, fallback: G) -> Partial<'a, T, filter::FallbackFilter<'a, T, F, G>>
+    where
+        F: SerializeFilter<T>,
+        G: SerializeFilter<T>,
+    {
+        Partial {
+            value: self.value,
+            filter: filter::FallbackFilter::new(self.filter, fallback),
+        }
+    }
+
+    /// Returns a value which serializes the selected fields as a map instead of a struct.
+    ///
+    /// This is useful for formats which treat structs and maps differently. Field names are serialized as string keys.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// # }
+    /// #
+    /// let user = User { name: "John Doe", age: 42 };
+    /// let filtered = user.with_fields(|u| [u.age]).as_map();
+    /// let value = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(value, serde_json::json!({ "age": 42 }));
+    /// ```
+    pub fn as_map(self) -> AsMap<Self> {
+        AsMap { partial: self }
+    }
+
+    /// Returns the number of fields which will be serialized.
+    ///
+    /// [`None`] means the count is dynamic and can't be known before serialization,
+    /// for example when the filter is based on a closure, the fields are only known at runtime
+    /// or some fields use `#[serde(skip_serializing_if = "...")]`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::SerializePartial;
+    /// #
+    /// # #[derive(Serialize, SerializePartial)]
+    /// # struct User {
+    /// #     name: &'static str,
+    /// #     age: u8,
+    /// #     email: &'static str,
+    /// # }
+    /// #
+    /// # const USER: User = User {
+    /// #     name: "John Doe",
+    /// #     age: 42,
+    /// #     email: "john.doe@example.com",
+    /// # };
+    /// #
+    /// assert_eq!(USER.with_fields(|u| [u.name, u.age]).len(), Some(2));
+    /// // selecting a field several times only counts it once
+    /// assert_eq!(USER.with_fields(|u| [u.name, u.name]).len(), Some(1));
+    /// assert_eq!(USER.without_fields(|u| [u.name, u.age]).len(), Some(1));
+    /// assert_eq!(USER.with_fields(|_| []).is_empty(), Some(true));
+    /// ```
+    pub fn len(&self) -> Option<usize>
+    where
+        F: SerializeFilter<T>,
+    {
+        // serde also counts the never serialized fields
+        let len = T::FIELD_COUNT.map(|count| count + T::NEVER_FIELDS.len());
+        filter::MarkedFilter::new(&self.filter).filtered_len(len)
+    }
+
+    /// Returns whether no fields will be serialized.
+    ///
+    /// See [`len`][Partial::len].
+    pub fn is_empty(&self) -> Option<bool>
+    where
+        F: SerializeFilter<T>,
+    {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Replaces the value while keeping the same filter.
+    pub fn map_value<G>(self, f: G) -> Self
+    where
+        G: FnOnce(&'a T) -> &'a T,
+    {
+        Partial {
+            value: f(self.value),
+            filter: self.filter,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Field<'a, T> {
+    /// Creates a new field.
+    ///
+    /// The name should be the serde field name and not the Rust field name.
+    pub const fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            rust_name: None,
+            type_name: None,
+            index: None,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Sets the Rust field name.
+    ///
+    /// When using the derive macro, the [`Fields`][SerializePartial::Fields] are created using this method.
+    pub const fn with_rust_name(self, rust_name: &'static str) -> Self {
+        Self {
+            rust_name: Some(rust_name),
+            ..self
+        }
+    }
+
+    /// Sets the name of the Rust type of the field.
+    ///
+    /// When using the derive macro, the [`Fields`][SerializePartial::Fields] are created using this method.
+    pub const fn with_type(self, type_name: &'static str) -> Self {
+        Self {
+            type_name: Some(type_name),
+            ..self
+        }
+    }
+
+    /// Sets the position of the field in the struct definition.
+    ///
+    /// When using the derive macro, the [`Fields`][SerializePartial::Fields] are created using this method.
+    pub const fn with_index(self, index: usize) -> Self {
+        Self {
+            index: Some(index),
+            ..self
+        }
+    }
+
+    /// Converts the field to a field of another type with the same name.
+    ///
+    /// This is mostly useful to select the fields of a newtype struct, whose [`Fields`][SerializePartial::Fields] are the ones of the wrapped type.
+    pub const fn cast<U: ?Sized>(self) -> Field<'a, U> {
+        Field {
+            name: self.name,
+            rust_name: self.rust_name,
+            type_name: self.type_name,
+            index: self.index,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the field name.
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the Rust field name, as it appears in the struct definition, if known.
     ///
-    /// The `select` closure receives an instance of [`Fields`][SerializePartial::Fields] which can than be used to select which fields should be serialized.
-    /// The closure can return any type which implements [`IntoIterator`]. This could be an array, but could also be a `Vec` or an [`Iterator`] with fields selected at runtime.
+    /// Raw identifiers are returned without their `r#` prefix.
+    /// Fields created with [`Field::new`] don't know their Rust name.
     ///
     /// ## Example
     ///
     /// ```
-    /// use serde::Serialize;
-    /// use serde_partial::SerializePartial;
-    ///
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
     /// #[derive(Serialize, SerializePartial)]
     /// #[serde(rename_all = "camelCase")]
     /// struct User {
     ///     full_name: &'static str,
-    ///     age: u8,
-    ///     #[serde(rename = "contact")]
-    ///     email: &'static str,
+    ///     r#type: u8,
     /// }
     ///
-    /// const USER: User = User {
-    ///     full_name: "John Doe",
-    ///     age: 42,
-    ///     email: "john.doe@example.com",
-    /// };
+    /// let fields = <User as SerializePartial>::Fields::FIELDS;
+    /// assert_eq!(fields.full_name.name(), "fullName");
+    /// assert_eq!(fields.full_name.rust_name(), Some("full_name"));
+    /// assert_eq!(fields.r#type.rust_name(), Some("type"));
+    /// assert_eq!(Field::<User>::new("fullName").rust_name(), None);
+    /// ```
+    pub const fn rust_name(&self) -> Option<&'static str> {
+        self.rust_name
+    }
+
+    /// Returns the name of the Rust type of the field, as it is written in the struct definition, if known.
     ///
-    /// // serialize only the `full_name` field
-    /// let filtered = USER.with_fields(|u| [u.full_name]);
-    /// let json = serde_json::to_value(&filtered).unwrap();
-    /// assert_eq!(
-    ///     json,
-    ///     serde_json::json!({
-    ///         "fullName": USER.full_name,
-    ///     })
-    /// );
+    /// Fields created with [`Field::new`] don't know their type.
     ///
-    /// // the field list doesn't have to be an array
-    /// // serialize every field with a name longer than 4 characters
-    /// let filtered = USER.with_fields(|u| u.into_iter().filter(|f| f.name().len() > 4));
-    /// let json = serde_json::to_value(&filtered).unwrap();
-    /// assert_eq!(
-    ///     json,
-    ///     serde_json::json!({
-    ///         "fullName": USER.full_name,
-    ///         "contact": USER.email,
-    ///     })
-    /// );
+    /// ## Example
     ///
-    /// // field names respect serde attributes
-    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.full_name.name(), "fullName");
-    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.age.name(), "age");
-    /// assert_eq!(<User as SerializePartial>::Fields::FIELDS.email.name(), "contact");
     /// ```
-    fn with_fields<F, I>(&'a self, select: F) -> Partial<'a, Self>
-    where
-        F: FnOnce(Self::Fields) -> I,
-        I: IntoIterator<Item = Field<'a, Self>>;
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// #[derive(Serialize, SerializePartial)]
+    /// struct User {
+    ///     name: &'static str,
+    ///     tags: Vec<(String, u8)>,
+    /// }
+    ///
+    /// let fields = <User as SerializePartial>::Fields::FIELDS;
+    /// assert_eq!(fields.name.type_name(), Some("&'static str"));
+    /// assert_eq!(fields.tags.type_name(), Some("Vec<(String, u8)>"));
+    /// assert_eq!(Field::<User>::new("name").with_type("String").type_name(), Some("String"));
+    /// ```
+    pub const fn type_name(&self) -> Option<&'static str> {
+        self.type_name
+    }
 
-    /// Same as [`with_fields`][SerializePartial::with_fields] but fields are opt-out instead of opt-in.
+    /// Returns the position of the field in the struct definition, if known.
+    ///
+    /// Fields skipped by serde don't count towards the position, and renaming a field doesn't change it.
+    /// Fields created with [`Field::new`], such as the ones of maps, don't know their position.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_partial::{Field, SerializePartial};
+    /// #
+    /// #[derive(Serialize, SerializePartial)]
+    /// struct User {
+    ///     #[serde(rename = "fullName")]
+    ///     name: &'static str,
+    ///     #[serde(skip)]
+    ///     password: &'static str,
+    ///     age: u8,
+    /// }
+    ///
+    /// let fields = <User as SerializePartial>::Fields::FIELDS;
+    /// assert_eq!(fields.name.index(), Some(0));
+    /// assert_eq!(fields.age.index(), Some(1));
+    /// assert_eq!(Field::<User>::new("age").index(), None);
+    ///
+    /// let by_index = fields.into_iter().find(|f| f.index() == Some(1));
+    /// assert_eq!(by_index, Some(fields.age));
+    /// ```
+    pub const fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Returns the field name as bytes.
+    pub const fn name_bytes(&self) -> &'a [u8] {
+        self.name.as_bytes()
+    }
+
+    /// Returns whether the field has the specified name.
     ///
     /// ## Example
     ///
@@ -106,107 +1807,39 @@ pub trait SerializePartial<'a>: Serialize {
     /// # use serde_partial::SerializePartial;
     /// #
     /// # #[derive(Serialize, SerializePartial)]
-    /// # #[serde(rename_all = "camelCase")]
     /// # struct User {
-    /// #     full_name: &'static str,
+    /// #     name: &'static str,
     /// #     age: u8,
-    /// #     #[serde(rename = "contact")]
     /// #     email: &'static str,
     /// # }
     /// #
     /// # const USER: User = User {
-    /// #     full_name: "John Doe",
+    /// #     name: "John Doe",
     /// #     age: 42,
     /// #     email: "john.doe@example.com",
     /// # };
     /// #
-    /// let filtered = USER.without_fields(|u| [u.email]);
+    /// let filtered = USER.with_fields(|u| u.into_iter().filter(|f| !f.is("email")));
     /// let json = serde_json::to_value(&filtered).unwrap();
-    /// assert_eq!(
-    ///     json,
-    ///     serde_json::json!({
-    ///         "fullName": USER.full_name,
-    ///         "age": USER.age,
-    ///     })
-    /// );
-    /// ```
-    fn without_fields<F, I>(
-        &'a self,
-        select: F,
-    ) -> Partial<'a, Self, filter::InverseFilter<'a, Self>>
-    where
-        F: FnOnce(Self::Fields) -> I,
-        I: IntoIterator<Item = Field<'a, Self>>,
-    {
-        let Partial { value, filter } = self.with_fields(select);
-        Partial {
-            value,
-            filter: filter::InverseFilter::new(filter),
-        }
-    }
-}
-
-/// A type which implements [`Serialize`] by forwarding the implementation to the value it references while skipping fields according to its filter.
-#[derive(Debug)]
-pub struct Partial<'a, T, F = <T as SerializePartial<'a>>::Filter>
-where
-    T: ?Sized + SerializePartial<'a>,
-{
-    /// The value to serialize.
-    pub value: &'a T,
-    /// The field filter to use.
-    pub filter: F,
-}
-
-/// Newtype around a field name for the specified type.
-#[repr(transparent)]
-pub struct Field<'a, T: ?Sized> {
-    name: &'a str,
-    _ty: PhantomData<T>,
-}
-
-impl<'a, T, F> Clone for Partial<'a, T, F>
-where
-    T: ?Sized + SerializePartial<'a>,
-    F: Clone,
-{
-    fn clone(&self) -> Self {
-        Self {
-            value: self.value,
-            filter: self.filter.clone(),
-        }
-    }
-}
-impl<'a, T, F> Copy for Partial<'a, T, F>
-where
-    T: ?Sized + SerializePartial<'a>,
-    F: Copy,
-{
-}
-
-impl<'a, T: ?Sized> Field<'a, T> {
-    /// Creates a new field.
+    /// assert_eq!(json, serde_json::json!({ "name": USER.name, "age": USER.age }));
     ///
-    /// The name should be the serde field name and not the Rust field name.
-    pub const fn new(name: &'a str) -> Self {
-        Self {
-            name,
-            _ty: PhantomData,
-        }
+    /// let filtered = USER.with_fields(|u| u.into_iter().filter(|f| f.matches_any(&["age", "email"])));
+    /// let json = serde_json::to_value(&filtered).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "age": USER.age, "email": USER.email }));
+    /// ```
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name
     }
 
-    /// Returns the field name.
-    pub const fn name(&self) -> &'a str {
-        self.name
+    /// Returns whether the field has any of the specified names.
+    pub fn matches_any(&self, names: &[&str]) -> bool {
+        names.iter().any(|name| self.is(name))
     }
 }
 
 impl<T: ?Sized> Clone for Field<'_, T> {
     fn clone(&self) -> Self {
-        Self {
-            name: self.name,
-            _ty: PhantomData,
-        }
+        *self
     }
 }
 impl<T: ?Sized> Copy for Field<'_, T> {}
@@ -215,6 +1848,9 @@ impl<T: ?Sized> fmt::Debug for Field<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Field")
             .field("name", &self.name)
+            .field("rust_name", &self.rust_name)
+            .field("type_name", &self.type_name)
+            .field("index", &self.index)
             .field("container", &core::any::type_name::<T>())
             .finish()
     }
@@ -233,7 +1869,7 @@ impl<T: ?Sized> hash::Hash for Field<'_, T> {
 }
 impl<T: ?Sized> PartialOrd for Field<'_, T> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        self.name.partial_cmp(other.name)
+        Some(self.cmp(other))
     }
 }
 impl<T: ?Sized> Ord for Field<'_, T> {
@@ -242,9 +1878,131 @@ impl<T: ?Sized> Ord for Field<'_, T> {
     }
 }
 
-impl<T, F> Serialize for Partial<'_, T, F>
+impl<'a> UnknownFieldError<'a> {
+    /// Creates a new error for the provided field name.
+    pub const fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            expected: &[],
+        }
+    }
+
+    /// Creates a new error for the provided field name which lists the valid field names.
+    pub const fn with_expected(name: &'a str, expected: &'static [&'static str]) -> Self {
+        Self { name, expected }
+    }
+
+    /// Returns the unknown field name.
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the valid field names, which might be empty if they aren't known.
+    pub const fn expected(&self) -> &'static [&'static str] {
+        self.expected
+    }
+}
+
+impl fmt::Display for UnknownFieldError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown field `{}`", self.name)?;
+        match self.expected {
+            [] => Ok(()),
+            [expected] => write!(f, ", expected `{}`", expected),
+            [first, rest @ ..] => {
+                write!(f, ", expected one of `{}`", first)?;
+                for expected in rest {
+                    write!(f, ", `{}`", expected)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Inverts the selection, like [`without_fields`][SerializePartial::without_fields].
+///
+/// ## Example
+///
+/// ```
+/// # use serde::Serialize;
+/// # use serde_partial::SerializePartial;
+/// #
+/// # #[derive(Serialize, SerializePartial)]
+/// # struct User {
+/// #     name: &'static str,
+/// #     age: u8,
+/// #     email: &'static str,
+/// # }
+/// #
+/// let user = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let public = user.with_fields(|u| [u.name, u.age]);
+/// let adults = user.with_fields(|u| [u.age, u.email]);
+///
+/// let json = serde_json::to_value(&(public & !adults)).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+///
+/// let json = serde_json::to_value(&(public | adults)).unwrap();
+/// assert_eq!(
+///     json,
+///     serde_json::json!({ "name": "John Doe", "age": 42, "email": "john.doe@example.com" })
+/// );
+/// ```
+impl<'a, T, F> ops::Not for Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    type Output = Partial<'a, T, filter::InverseFilter<'a, T, F>>;
+
+    fn not(self) -> Self::Output {
+        self.map_filter(filter::InverseFilter::new)
+    }
+}
+
+/// Only selects the fields selected by both partials, like [`chain`][Partial::chain].
+///
+/// Both partials should reference the same value, and the value of the left hand side is used.
+impl<'a, T, F, G> ops::BitAnd<Partial<'a, T, G>> for Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    type Output = Partial<'a, T, filter::AndFilter<T, F, G>>;
+
+    fn bitand(self, rhs: Partial<'a, T, G>) -> Self::Output {
+        debug_assert!(
+            ptr::eq(self.value, rhs.value),
+            "partials reference different values"
+        );
+        self.chain(rhs.filter)
+    }
+}
+
+/// Selects the fields selected by either partial, like [`chain_or`][Partial::chain_or].
+///
+/// Both partials should reference the same value, and the value of the left hand side is used.
+impl<'a, T, F, G> ops::BitOr<Partial<'a, T, G>> for Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+    G: SerializeFilter<T>,
+{
+    type Output = Partial<'a, T, filter::OrFilter<T, F, G>>;
+
+    fn bitor(self, rhs: Partial<'a, T, G>) -> Self::Output {
+        debug_assert!(
+            ptr::eq(self.value, rhs.value),
+            "partials reference different values"
+        );
+        self.chain_or(rhs.filter)
+    }
+}
+
+impl<'a, T, F> Serialize for Partial<'a, T, F>
 where
-    T: ?Sized + for<'a> SerializePartial<'a>,
+    T: ?Sized + SerializePartial<'a>,
     F: SerializeFilter<T>,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -253,7 +2011,7 @@ where
     {
         let serializer = PartialSerializer {
             s: serializer,
-            filter: &self.filter,
+            filter: &filter::MarkedFilter::new(&self.filter),
             _ty: PhantomData,
         };
         self.value.serialize(serializer)
@@ -264,48 +2022,115 @@ struct PartialSerializer<'a, S, T, F>
 where
     S: Serializer,
     T: ?Sized,
+    F: ?Sized,
 {
     s: S,
     filter: &'a F,
     _ty: PhantomData<T>,
 }
 
+/// A value which is serialized through a [`PartialSerializer`].
+struct Filtered<'a, V, T, F>
+where
+    V: ?Sized,
+    T: ?Sized,
+    F: ?Sized,
+{
+    value: &'a V,
+    filter: &'a F,
+    _ty: PhantomData<T>,
+}
+
+impl<'a, V, T, F> Filtered<'a, V, T, F>
+where
+    V: ?Sized,
+    T: ?Sized,
+    F: ?Sized,
+{
+    fn new(value: &'a V, filter: &'a F) -> Self {
+        Self {
+            value,
+            filter,
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<V, T, F> Serialize for Filtered<'_, V, T, F>
+where
+    V: ?Sized + Serialize,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let serializer = PartialSerializer {
+            s: serializer,
+            filter: self.filter,
+            _ty: PhantomData::<T>,
+        };
+        self.value.serialize(serializer)
+    }
+}
+
 impl<'a, S, T, F> Serializer for PartialSerializer<'a, S, T, F>
 where
     S: Serializer,
-    T: ?Sized + for<'p> SerializePartial<'p>,
-    F: SerializeFilter<T>,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
 {
     type Ok = S::Ok;
     type Error = S::Error;
 
     type SerializeStruct = serde_struct::PartialSerializeStruct<'a, S, T, F>;
     type SerializeMap = serde_map::PartialSerializeMap<'a, S, T, F>;
+    type SerializeSeq = serde_seq::PartialSerializeSeq<'a, S, T, F>;
+    type SerializeStructVariant = serde_struct::PartialSerializeStructVariant<'a, S, T, F>;
 
     fn serialize_struct(
         self,
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        let PartialSerializer { s, filter, _ty } = self;
-        let len = filter.filtered_len(Some(len)).unwrap_or(len);
-        let ss = s.serialize_struct(name, len)?;
-        Ok(Self::SerializeStruct { ss, filter, _ty })
+        let cursor = serde_struct::FieldCursor::new(self.filter, len, self.s.is_human_readable());
+        let ss = self.s.serialize_struct(name, cursor.len(len))?;
+        Ok(Self::SerializeStruct { ss, cursor })
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         let PartialSerializer { s, filter, _ty } = self;
-        let len = filter.filtered_len(len).or(len);
+        let human_readable = s.is_human_readable();
+        let len = filter.filtered_len_hr(len, human_readable).or(len);
         let sm = s.serialize_map(len)?;
-        Ok(Self::SerializeMap { sm, filter, _ty })
+        Ok(Self::SerializeMap {
+            sm,
+            filter,
+            human_readable,
+            _ty,
+        })
     }
     // collect_map not implemented because we explicitly want serde's default implementation
 
-    type SerializeSeq = S::SerializeSeq;
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let PartialSerializer { s, filter, _ty } = self;
+        let ss = s.serialize_seq(len)?;
+        Ok(Self::SerializeSeq { ss, filter, _ty })
+    }
+    // collect_seq not implemented because we explicitly want serde's default implementation
+
+    fn serialize_some<TT>(self, value: &TT) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.s
+            .serialize_some(&Filtered::<_, T, F>::new(value, self.filter))
+    }
+
     type SerializeTuple = S::SerializeTuple;
     type SerializeTupleStruct = S::SerializeTupleStruct;
     type SerializeTupleVariant = S::SerializeTupleVariant;
-    type SerializeStructVariant = S::SerializeStructVariant;
 
     fn is_human_readable(&self) -> bool {
         self.s.is_human_readable()
@@ -361,12 +2186,6 @@ where
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         self.s.serialize_none()
     }
-    fn serialize_some<TT: ?Sized>(self, value: &TT) -> Result<Self::Ok, Self::Error>
-    where
-        TT: Serialize,
-    {
-        self.s.serialize_some(value)
-    }
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
         self.s.serialize_unit()
     }
@@ -381,17 +2200,18 @@ where
     ) -> Result<Self::Ok, Self::Error> {
         self.s.serialize_unit_variant(name, index, variant)
     }
-    fn serialize_newtype_struct<TT: ?Sized>(
+    fn serialize_newtype_struct<TT>(
         self,
         name: &'static str,
         value: &TT,
     ) -> Result<Self::Ok, Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
-        self.s.serialize_newtype_struct(name, value)
+        self.s
+            .serialize_newtype_struct(name, &Filtered::<_, T, F>::new(value, self.filter))
     }
-    fn serialize_newtype_variant<TT: ?Sized>(
+    fn serialize_newtype_variant<TT>(
         self,
         name: &'static str,
         index: u32,
@@ -399,14 +2219,11 @@ where
         value: &TT,
     ) -> Result<Self::Ok, Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
         self.s
             .serialize_newtype_variant(name, index, variant, value)
     }
-    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        self.s.serialize_seq(len)
-    }
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
         self.s.serialize_tuple(len)
     }
@@ -433,19 +2250,16 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.s.serialize_struct_variant(name, index, variant, len)
+        let cursor = serde_struct::FieldCursor::new(self.filter, len, self.s.is_human_readable());
+        let ssv = self
+            .s
+            .serialize_struct_variant(name, index, variant, cursor.len(len))?;
+        Ok(Self::SerializeStructVariant { ssv, cursor })
     }
-    fn collect_str<TT: ?Sized>(self, value: &TT) -> Result<Self::Ok, Self::Error>
+    fn collect_str<TT>(self, value: &TT) -> Result<Self::Ok, Self::Error>
     where
-        TT: fmt::Display,
+        TT: ?Sized + fmt::Display,
     {
         self.s.collect_str(value)
     }
-    fn collect_seq<I>(self, iter: I) -> Result<Self::Ok, Self::Error>
-    where
-        I: IntoIterator,
-        <I as IntoIterator>::Item: Serialize,
-    {
-        self.s.collect_seq(iter)
-    }
 }
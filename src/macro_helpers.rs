@@ -0,0 +1,86 @@
+//! Helpers for hand-written [`Serialize`] implementations
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::ser::{Serialize, SerializeStruct, Serializer};
+//! use serde_partial::{macro_helpers::apply_filter, Field, SerializeFilter};
+//!
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! struct OnlyX;
+//! impl SerializeFilter<Point> for OnlyX {
+//!     fn skip(&self, field: Field<'_, Point>) -> bool {
+//!         field.name() != "x"
+//!     }
+//!
+//!     fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+//!         Some(1)
+//!     }
+//! }
+//!
+//! struct PartialPoint<'a>(&'a Point);
+//! impl Serialize for PartialPoint<'_> {
+//!     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+//!     where
+//!         S: Serializer,
+//!     {
+//!         let mut ss = serializer.serialize_struct("Point", 1)?;
+//!         apply_filter(&mut ss, &OnlyX, "x", &self.0.x)?;
+//!         apply_filter(&mut ss, &OnlyX, "y", &self.0.y)?;
+//!         ss.end()
+//!     }
+//! }
+//!
+//! let json = serde_json::to_value(&PartialPoint(&Point { x: 1, y: 2 })).unwrap();
+//! assert_eq!(json, serde_json::json!({ "x": 1 }));
+//! ```
+
+use serde::ser::{Serialize, SerializeStruct};
+
+use crate::{Field, Filtered, SerializeFilter};
+
+/// Serializes or skips a struct field depending on whether the filter selects it.
+///
+/// If the filter provides a [`nested`][SerializeFilter::nested] filter for the field, it is applied to the value.
+pub fn apply_filter<S, T, F, V>(
+    ss: &mut S,
+    filter: &F,
+    key: &'static str,
+    value: &V,
+) -> Result<(), S::Error>
+where
+    S: SerializeStruct,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+    V: ?Sized + Serialize,
+{
+    if filter.skip(Field::new(key)) {
+        ss.skip_field(key)
+    } else {
+        serialize_selected(ss, filter, key, value)
+    }
+}
+
+/// Serializes a struct field which the filter selects, applying its nested filter if any.
+pub(crate) fn serialize_selected<S, T, F, V>(
+    ss: &mut S,
+    filter: &F,
+    key: &'static str,
+    value: &V,
+) -> Result<(), S::Error>
+where
+    S: SerializeStruct,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+    V: ?Sized + Serialize,
+{
+    if let Some(filter) = filter.nested(Field::new(key)) {
+        ss.serialize_field(key, &Filtered::<_, (), _>::new(value, filter))
+    } else {
+        ss.serialize_field(key, value)
+    }
+}
@@ -1,46 +1,50 @@
-use core::marker::PhantomData;
+use core::{fmt, marker::PhantomData};
 
 use serde::ser::{Error, Impossible, Serialize, SerializeMap, Serializer};
 
-use crate::{Field, SerializeFilter, SerializePartial};
+use crate::{Field, SerializeFilter};
 
 pub(crate) struct PartialSerializeMap<'a, S, T, F>
 where
     S: Serializer,
     T: ?Sized,
+    F: ?Sized,
 {
     pub(crate) sm: S::SerializeMap,
     pub(crate) filter: &'a F,
+    pub(crate) human_readable: bool,
     pub(crate) _ty: PhantomData<T>,
 }
 
 struct KeySerializer<'a, T, F, E>
 where
     T: ?Sized,
+    F: ?Sized,
 {
     filter: &'a F,
+    human_readable: bool,
     _ty: PhantomData<(&'a T, E)>,
 }
 
 impl<'a, S, T, F> SerializeMap for PartialSerializeMap<'a, S, T, F>
 where
     S: Serializer,
-    T: ?Sized + for<'p> SerializePartial<'p>,
-    F: SerializeFilter<T>,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
 {
     type Ok = <S::SerializeMap as SerializeMap>::Ok;
     type Error = <S::SerializeMap as SerializeMap>::Error;
 
-    fn serialize_key<K: ?Sized>(&mut self, _key: &K) -> Result<(), Self::Error>
+    fn serialize_key<K>(&mut self, _key: &K) -> Result<(), Self::Error>
     where
-        K: Serialize,
+        K: ?Sized + Serialize,
     {
         Err(Self::Error::custom("cannot perform partial serialization of lone keys, use `serialize_entry` instead if possible"))
     }
 
-    fn serialize_value<V: ?Sized>(&mut self, _value: &V) -> Result<(), Self::Error>
+    fn serialize_value<V>(&mut self, _value: &V) -> Result<(), Self::Error>
     where
-        V: Serialize,
+        V: ?Sized + Serialize,
     {
         Err(Self::Error::custom("cannot perform partial serialization of lone values, use `serialize_entry` instead if possible"))
     }
@@ -49,17 +53,14 @@ where
         self.sm.end()
     }
 
-    fn serialize_entry<K: ?Sized, V: ?Sized>(
-        &mut self,
-        key: &K,
-        value: &V,
-    ) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
     where
-        K: Serialize,
-        V: Serialize,
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
     {
         let skip = key.serialize(KeySerializer::<'_, T, F, Self::Error> {
             filter: self.filter,
+            human_readable: self.human_readable,
             _ty: PhantomData,
         })?;
         if skip {
@@ -70,19 +71,58 @@ where
     }
 }
 
-static KEY_ERR: &str = "key should serialize to a string";
+static KEY_ERR: &str = "key should serialize to a string, a primitive or a unit variant";
+
+impl<'a, T, F, E> KeySerializer<'a, T, F, E>
+where
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+    E: Error,
+{
+    /// Renders a non-string key the same way formats like JSON do before passing it to the filter.
+    fn skip_display<D: fmt::Display>(self, key: D) -> Result<bool, E> {
+        let mut buf = KeyBuf {
+            buf: [0; KEY_BUF_LEN],
+            len: 0,
+        };
+        fmt::write(&mut buf, format_args!("{}", key)).map_err(|_| E::custom(KEY_ERR))?;
+        // only complete `&str`s are written
+        let key = core::str::from_utf8(&buf.buf[..buf.len]).map_err(|_| E::custom(KEY_ERR))?;
+        self.serialize_str(key)
+    }
+}
+
+/// Large enough for any primitive key.
+const KEY_BUF_LEN: usize = 40;
+
+struct KeyBuf {
+    buf: [u8; KEY_BUF_LEN],
+    len: usize,
+}
+
+impl fmt::Write for KeyBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > KEY_BUF_LEN {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
 
 impl<'a, T, F, E> Serializer for KeySerializer<'a, T, F, E>
 where
-    T: ?Sized + for<'p> SerializePartial<'p>,
-    F: SerializeFilter<T>,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
     E: Error,
 {
     type Ok = bool;
     type Error = E;
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Ok(self.filter.skip(Field::new(v)))
+        Ok(self.filter.skip_hr(Field::new(v), self.human_readable))
     }
 
     type SerializeSeq = Impossible<Self::Ok, Self::Error>;
@@ -93,32 +133,32 @@ where
     type SerializeStruct = Impossible<Self::Ok, Self::Error>;
     type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
 
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
-    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
     fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
         Err(Self::Error::custom(KEY_ERR))
@@ -126,8 +166,8 @@ where
     fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
         Err(Self::Error::custom(KEY_ERR))
     }
-    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.skip_display(v)
     }
     fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
         Err(Self::Error::custom(KEY_ERR))
@@ -135,9 +175,9 @@ where
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         Err(Self::Error::custom(KEY_ERR))
     }
-    fn serialize_some<TT: ?Sized>(self, _value: &TT) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<TT>(self, _value: &TT) -> Result<Self::Ok, Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
         Err(Self::Error::custom(KEY_ERR))
     }
@@ -151,21 +191,21 @@ where
         self,
         _name: &'static str,
         _index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom(KEY_ERR))
+        self.serialize_str(variant)
     }
-    fn serialize_newtype_struct<TT: ?Sized>(
+    fn serialize_newtype_struct<TT>(
         self,
         _name: &'static str,
-        _value: &TT,
+        value: &TT,
     ) -> Result<Self::Ok, Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
-        Err(Self::Error::custom(KEY_ERR))
+        value.serialize(self)
     }
-    fn serialize_newtype_variant<TT: ?Sized>(
+    fn serialize_newtype_variant<TT>(
         self,
         _name: &'static str,
         _index: u32,
@@ -173,7 +213,7 @@ where
         _value: &TT,
     ) -> Result<Self::Ok, Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
         Err(Self::Error::custom(KEY_ERR))
     }
@@ -218,4 +258,51 @@ where
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         Err(Self::Error::custom(KEY_ERR))
     }
+    #[cfg(not(feature = "alloc"))]
+    fn collect_str<TT>(self, _value: &TT) -> Result<Self::Ok, Self::Error>
+    where
+        TT: ?Sized + core::fmt::Display,
+    {
+        Err(Self::Error::custom(KEY_ERR))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use core::marker::PhantomData;
+
+    use serde::Serialize;
+
+    use alloc::collections::BTreeMap;
+    use std::collections::HashSet;
+
+    use crate::PartialSerializer;
+
+    #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    fn filtered<K: Serialize>(map: &BTreeMap<K, u8>, keys: &[&str]) -> serde_json::Value {
+        let filter = keys.iter().copied().collect::<HashSet<&str>>();
+        map.serialize(PartialSerializer {
+            s: serde_json::value::Serializer,
+            filter: &filter,
+            _ty: PhantomData::<()>,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn non_string_keys() {
+        let map = BTreeMap::from([(1, 1), (-2, 2), (30, 3)]);
+        assert_eq!(
+            filtered(&map, &["1", "-2"]),
+            serde_json::json!({ "-2": 2, "1": 1 })
+        );
+
+        let map = BTreeMap::from([(Color::Red, 1), (Color::Blue, 2)]);
+        assert_eq!(filtered(&map, &["Blue"]), serde_json::json!({ "Blue": 2 }));
+    }
 }
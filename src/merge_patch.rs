@@ -0,0 +1,78 @@
+//! [RFC 7396](https://www.rfc-editor.org/rfc/rfc7396) JSON Merge Patch generation
+//!
+//! Merge patches describe the changes between two JSON documents,
+//! using `null` to mark the members which should be removed.
+//! They are the usual request body of `PATCH` endpoints.
+
+use serde_json::{Error, Map, Value};
+
+use crate::{Partial, SerializeFilter, SerializePartial};
+
+/// Returns the merge patch turning the serialized `base` into the serialized `update`.
+///
+/// Fields only serialized by `update` or whose value changed appear with their new value,
+/// and fields only serialized by `base` appear as `null`. Unchanged fields are omitted.
+/// Nested objects are compared recursively.
+///
+/// Merge patches can't represent setting a member to `null`,
+/// so fields serialized as `null` by `update` are treated as removed.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{merge_patch::merge_patch, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let before = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+/// let after = User { name: "John Doe", age: 43, email: "john.doe@example.com" };
+///
+/// let patch = merge_patch(
+///     &before.with_fields(|u| [u.name, u.email]),
+///     &after.with_fields(|u| [u.name, u.age]),
+/// )
+/// .unwrap();
+/// assert_eq!(patch, serde_json::json!({ "age": 43, "email": null }));
+/// ```
+pub fn merge_patch<'a, T, F>(
+    base: &Partial<'a, T, F>,
+    update: &Partial<'a, T, F>,
+) -> Result<Value, Error>
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    let base = serde_json::to_value(base)?;
+    let update = serde_json::to_value(update)?;
+    Ok(diff(base, update))
+}
+
+fn diff(base: Value, update: Value) -> Value {
+    match (base, update) {
+        (Value::Object(mut base), Value::Object(update)) => {
+            let mut patch = Map::new();
+            for (key, value) in update {
+                match base.remove(&key) {
+                    Some(old) if old == value => {}
+                    Some(old @ Value::Object(_)) if value.is_object() => {
+                        patch.insert(key, diff(old, value));
+                    }
+                    _ => {
+                        patch.insert(key, value);
+                    }
+                }
+            }
+            for (key, _) in base {
+                patch.insert(key, Value::Null);
+            }
+            Value::Object(patch)
+        }
+        (_, update) => update,
+    }
+}
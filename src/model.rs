@@ -0,0 +1,221 @@
+//! Partially serialized models
+
+use core::marker::PhantomData;
+
+use serde::{
+    de::{Deserialize, Deserializer},
+    ser::{Serialize, Serializer},
+};
+
+use crate::{
+    filter::{InverseFilter, MarkedFilter},
+    Field, PartialSerializer, SerializeFilter, SerializePartial,
+};
+
+/// A type which owns a value and the filter to use when serializing it.
+///
+/// [`Model`] always serializes as if it was a [`Partial`][crate::Partial] referencing its value.
+/// This makes it convenient to use as the return type of API routes.
+///
+/// When deserializing, the value is deserialized normally and the filter is initialized with [`Default::default`].
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{model::Model, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// let user = User { name: "John Doe", age: 42 };
+/// let filter = user.with_fields(|u| [u.name]).filter;
+/// let model = Model::new(user, filter);
+/// let json = serde_json::to_value(&model).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Model<T, F = <T as SerializePartial<'static>>::Filter>
+where
+    T: SerializePartial<'static>,
+{
+    /// The value to serialize.
+    pub value: T,
+    /// The field filter to use.
+    pub filter: F,
+}
+
+impl<T, F> Model<T, F>
+where
+    T: SerializePartial<'static>,
+{
+    /// Creates a new model from a value and a filter.
+    pub fn new(value: T, filter: F) -> Self {
+        Self { value, filter }
+    }
+
+    /// Returns the value, discarding the filter.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, F> Serialize for Model<T, F>
+where
+    T: SerializePartial<'static>,
+    F: SerializeFilter<T>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let serializer = PartialSerializer {
+            s: serializer,
+            filter: &MarkedFilter::new(&self.filter),
+            _ty: PhantomData,
+        };
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T, F> Deserialize<'de> for Model<T, F>
+where
+    T: SerializePartial<'static> + Deserialize<'de>,
+    F: Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|value| Self {
+            value,
+            filter: F::default(),
+        })
+    }
+}
+
+/// Trait implemented by all [`SerializePartial`] types which makes it possible to partially serialize owned values.
+///
+/// This is useful when a value is created purely to be serialized partially, in which case borrowing it is unnecessary.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{IntoPartial, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+/// }
+///
+/// fn user() -> User {
+///     User { name: "John Doe", age: 42 }
+/// }
+///
+/// let json = serde_json::to_value(user().partial()).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe", "age": 42 }));
+///
+/// let json = serde_json::to_value(user().partial().with_fields(|u| [u.name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "name": "John Doe" }));
+///
+/// let json = serde_json::to_value(user().partial().without_fields(|u| [u.name])).unwrap();
+/// assert_eq!(json, serde_json::json!({ "age": 42 }));
+/// ```
+pub trait IntoPartial<'a>: SerializePartial<'a> + Sized {
+    /// Wraps the value in a [`PartialOwned`] which serializes all of its fields until some are selected.
+    fn partial(self) -> PartialOwned<Self, InverseFilter<'a, Self>>
+    where
+        Self::Filter: Default,
+    {
+        PartialOwned {
+            value: self,
+            filter: InverseFilter::new(Self::Filter::default()),
+        }
+    }
+}
+
+impl<'a, T> IntoPartial<'a> for T where T: SerializePartial<'a> {}
+
+/// A type which owns a value and implements [`Serialize`] by skipping fields according to its filter.
+///
+/// Unlike [`Model`], selecting fields consumes the value and returns a new [`PartialOwned`].
+/// Fields can only be selected for types whose [`Fields`][SerializePartial::Fields] and [`Filter`][SerializePartial::Filter] don't borrow from the value,
+/// which is always the case when using the derive macro.
+#[derive(Debug, Clone, Copy)]
+pub struct PartialOwned<T, F> {
+    /// The value to serialize.
+    pub value: T,
+    /// The field filter to use.
+    pub filter: F,
+}
+
+impl<T, F> PartialOwned<T, F> {
+    /// Selects the fields to serialize, replacing the current filter.
+    ///
+    /// See [`SerializePartial::with_fields`].
+    pub fn with_fields<S, I, Fs, G>(self, select: S) -> PartialOwned<T, G>
+    where
+        T: for<'b> SerializePartial<'b, Fields = Fs, Filter = G>,
+        S: FnOnce(Fs) -> I,
+        I: IntoIterator<Item = Field<'static, T>>,
+    {
+        // shortens the lifetime of the selected fields to the one of the borrow
+        #[allow(clippy::map_identity)]
+        let filter = self
+            .value
+            .with_fields(|fields| select(fields).into_iter().map(|f| f))
+            .filter;
+        PartialOwned {
+            value: self.value,
+            filter,
+        }
+    }
+
+    /// Selects the fields to skip, replacing the current filter.
+    ///
+    /// See [`SerializePartial::without_fields`].
+    pub fn without_fields<'a, S, I, Fs, G>(
+        self,
+        select: S,
+    ) -> PartialOwned<T, InverseFilter<'a, T, G>>
+    where
+        T: for<'b> SerializePartial<'b, Fields = Fs, Filter = G>,
+        G: SerializeFilter<T>,
+        S: FnOnce(Fs) -> I,
+        I: IntoIterator<Item = Field<'static, T>>,
+    {
+        let PartialOwned { value, filter } = self.with_fields(select);
+        PartialOwned {
+            value,
+            filter: InverseFilter::new(filter),
+        }
+    }
+
+    /// Returns the value, discarding the filter.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, F> Serialize for PartialOwned<T, F>
+where
+    T: Serialize,
+    F: SerializeFilter<T>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let serializer = PartialSerializer {
+            s: serializer,
+            filter: &self.filter,
+            _ty: PhantomData,
+        };
+        self.value.serialize(serializer)
+    }
+}
@@ -0,0 +1,92 @@
+//! MongoDB integration
+//!
+//! Projections make it possible to only fetch the fields which are going to be serialized,
+//! using the same filter for both the query and the serialization.
+//!
+//! MongoDB treats an empty projection as selecting all the fields,
+//! so filters which don't select any field should be handled separately.
+
+use serde_json::{Map, Value};
+
+use crate::{Field, SerializeFilter, SerializePartial};
+
+/// Returns a MongoDB inclusion projection of the fields selected by the filter.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{mongodb::filter_to_mongo_projection, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// const USER: User = User {
+///     full_name: "John Doe",
+///     age: 42,
+///     email: "john.doe@example.com",
+/// };
+///
+/// let filtered = USER.with_fields(|u| [u.full_name, u.age]);
+/// assert_eq!(
+///     filter_to_mongo_projection::<User, _>(&filtered.filter),
+///     serde_json::json!({ "fullName": 1, "age": 1 })
+/// );
+/// ```
+pub fn filter_to_mongo_projection<'a, T, F>(filter: &F) -> Value
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    projection::<T, F>(filter, false)
+}
+
+/// Returns a MongoDB exclusion projection of the fields skipped by the filter.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{mongodb::filter_to_mongo_exclusion, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// const USER: User = User { name: "John Doe", age: 42, email: "john.doe@example.com" };
+///
+/// let filtered = USER.with_fields(|u| [u.name]);
+/// assert_eq!(
+///     filter_to_mongo_exclusion::<User, _>(&filtered.filter),
+///     serde_json::json!({ "age": 0, "email": 0 })
+/// );
+/// ```
+pub fn filter_to_mongo_exclusion<'a, T, F>(filter: &F) -> Value
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    projection::<T, F>(filter, true)
+}
+
+fn projection<'a, T, F>(filter: &F, exclusion: bool) -> Value
+where
+    T: ?Sized + SerializePartial<'a>,
+    F: SerializeFilter<T>,
+{
+    let mut projection = Map::new();
+    for name in T::field_names() {
+        if filter.skip(Field::new(name)) == exclusion {
+            projection.insert((*name).into(), Value::from(u8::from(!exclusion)));
+        }
+    }
+    Value::Object(projection)
+}
@@ -0,0 +1,156 @@
+//! Glob pattern based field filtering
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//! use serde_partial::{pattern::PatternFilter, Partial, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct Post {
+//!     title: &'static str,
+//!     created_at: u64,
+//!     updated_at: u64,
+//!     internal_score: u32,
+//! }
+//!
+//! let post = Post { title: "Hello", created_at: 1, updated_at: 2, internal_score: 3 };
+//!
+//! let filter = PatternFilter::include("*_at");
+//! let json = serde_json::to_value(&Partial { value: &post, filter }).unwrap();
+//! assert_eq!(json, serde_json::json!({ "created_at": 1, "updated_at": 2 }));
+//!
+//! let filter = PatternFilter::exclude("internal_*");
+//! let json = serde_json::to_value(&Partial { value: &post, filter }).unwrap();
+//! assert_eq!(json, serde_json::json!({ "title": "Hello", "created_at": 1, "updated_at": 2 }));
+//! ```
+
+use core::{fmt, marker::PhantomData};
+
+use crate::{Field, SerializeFilter};
+
+/// A [`SerializeFilter`] which matches field names against a glob pattern.
+///
+/// Patterns support `*`, which matches any sequence of characters, and `?`, which matches a single character.
+/// Every other character matches itself.
+pub struct PatternFilter<'p, T: ?Sized> {
+    pattern: &'p str,
+    include: bool,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<'p, T: ?Sized> PatternFilter<'p, T> {
+    /// Creates a filter which only serializes the fields matching the pattern.
+    pub const fn include(pattern: &'p str) -> Self {
+        Self {
+            pattern,
+            include: true,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Creates a filter which only serializes the fields not matching the pattern.
+    pub const fn exclude(pattern: &'p str) -> Self {
+        Self {
+            pattern,
+            include: false,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the pattern.
+    pub const fn pattern(&self) -> &'p str {
+        self.pattern
+    }
+}
+
+impl<T: ?Sized> SerializeFilter<T> for PatternFilter<'_, T> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        glob_match(self.pattern, field.name()) != self.include
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+}
+
+impl<T: ?Sized> Clone for PatternFilter<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Copy for PatternFilter<'_, T> {}
+
+impl<T: ?Sized> fmt::Debug for PatternFilter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PatternFilter")
+            .field("pattern", &self.pattern)
+            .field("include", &self.include)
+            .finish()
+    }
+}
+
+/// Returns whether the name matches the glob pattern.
+///
+/// Backtracks to the last `*` on mismatches, which keeps the matching linear in most cases without allocating.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (mut p, mut n) = (pattern, name);
+    // pattern after the last `*` and the name position it is currently matched from
+    let mut backtrack: Option<(&str, &str)> = None;
+
+    loop {
+        let mut pattern_chars = p.chars();
+        match pattern_chars.next() {
+            Some('*') => {
+                p = pattern_chars.as_str();
+                backtrack = Some((p, n));
+                continue;
+            }
+            Some(pc) => {
+                let mut name_chars = n.chars();
+                if let Some(nc) = name_chars.next() {
+                    if pc == '?' || pc == nc {
+                        p = pattern_chars.as_str();
+                        n = name_chars.as_str();
+                        continue;
+                    }
+                }
+            }
+            None if n.is_empty() => return true,
+            None => {}
+        }
+
+        match backtrack {
+            Some((bp, bn)) => {
+                let mut name_chars = bn.chars();
+                if name_chars.next().is_none() {
+                    return false;
+                }
+                p = bp;
+                n = name_chars.as_str();
+                backtrack = Some((bp, n));
+            }
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("name", "name"));
+        assert!(!glob_match("name", "names"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*_at", "created_at"));
+        assert!(!glob_match("*_at", "created_on"));
+        assert!(glob_match("internal_*", "internal_score"));
+        assert!(glob_match("?ge", "age"));
+        assert!(!glob_match("?ge", "ge"));
+        assert!(glob_match("a*b*c", "axxbyybzc"));
+        assert!(!glob_match("a*b*c", "axxbyyb"));
+        assert!(glob_match("é?", "éà"));
+    }
+}
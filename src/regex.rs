@@ -0,0 +1,100 @@
+//! Regular expression based field filtering
+//!
+//! Compiling a regular expression is much more expensive than matching it,
+//! so filters should be created once and reused for every value, for example by storing them in a static
+//! and building each [`Partial`][crate::Partial] from a reference to the filter.
+//!
+//! ## Example
+//!
+//! ```
+//! use regex_crate::Regex;
+//! use serde::Serialize;
+//! use serde_partial::{regex::RegexFilter, Partial, SerializePartial};
+//!
+//! #[derive(Serialize, SerializePartial)]
+//! struct Post {
+//!     title: &'static str,
+//!     meta_author: &'static str,
+//!     meta_tags: &'static [&'static str],
+//! }
+//!
+//! let post = Post { title: "Hello", meta_author: "John Doe", meta_tags: &["greeting"] };
+//!
+//! let filter = RegexFilter::include(Regex::new("^meta_").unwrap());
+//! let json = serde_json::to_value(&Partial { value: &post, filter: &filter }).unwrap();
+//! assert_eq!(json, serde_json::json!({ "meta_author": "John Doe", "meta_tags": ["greeting"] }));
+//!
+//! let filter = RegexFilter::exclude(Regex::new("^meta_").unwrap());
+//! let json = serde_json::to_value(&Partial { value: &post, filter: &filter }).unwrap();
+//! assert_eq!(json, serde_json::json!({ "title": "Hello" }));
+//! ```
+
+use core::{fmt, marker::PhantomData};
+
+use regex_crate::Regex;
+
+use crate::{Field, SerializeFilter};
+
+/// A [`SerializeFilter`] which matches field names against a regular expression.
+///
+/// The regular expression can match anywhere in the name unless it is anchored.
+/// The filtered length can't be known ahead of time.
+pub struct RegexFilter<T: ?Sized> {
+    regex: Regex,
+    include: bool,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> RegexFilter<T> {
+    /// Creates a filter which only serializes the fields matching the regular expression.
+    pub fn include(regex: Regex) -> Self {
+        Self {
+            regex,
+            include: true,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Creates a filter which only serializes the fields not matching the regular expression.
+    pub fn exclude(regex: Regex) -> Self {
+        Self {
+            regex,
+            include: false,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the regular expression.
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl<T: ?Sized> SerializeFilter<T> for RegexFilter<T> {
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        self.regex.is_match(field.name()) != self.include
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        None
+    }
+}
+
+impl<T: ?Sized> Clone for RegexFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            regex: self.regex.clone(),
+            include: self.include,
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for RegexFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegexFilter")
+            .field("regex", &self.regex.as_str())
+            .field("include", &self.include)
+            .finish()
+    }
+}
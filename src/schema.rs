@@ -0,0 +1,62 @@
+//! JSON Schema integration
+//!
+//! [`JsonSchema`] describes a type statically, so it can't depend on the filter of a [`Partial`].
+//! Instead, [`Partial::json_schema`] generates the full schema of the value type and removes the properties skipped by the filter.
+//! The rest of the schema, including everything derived from serde attributes, is left untouched.
+//!
+//! ## Example
+//!
+//! ```
+//! use schemars_crate::JsonSchema;
+//! use serde::Serialize;
+//! use serde_partial::SerializePartial;
+//!
+//! #[derive(Serialize, SerializePartial, JsonSchema)]
+//! #[schemars(crate = "schemars_crate")]
+//! #[serde(rename_all = "camelCase")]
+//! struct User {
+//!     full_name: &'static str,
+//!     age: u8,
+//!     nickname: Option<&'static str>,
+//! }
+//!
+//! let user = User { full_name: "John Doe", age: 42, nickname: None };
+//! let schema = user.with_fields(|u| [u.full_name, u.nickname]).json_schema();
+//!
+//! let object = schema.schema.object.unwrap();
+//! assert_eq!(
+//!     object.properties.keys().collect::<Vec<_>>(),
+//!     ["fullName", "nickname"]
+//! );
+//! assert_eq!(object.required.iter().collect::<Vec<_>>(), ["fullName"]);
+//! ```
+
+use schemars_crate::{gen::SchemaGenerator, schema::RootSchema, JsonSchema};
+
+use crate::{Field, Partial, SerializeFilter, SerializePartial};
+
+impl<'a, T, F> Partial<'a, T, F>
+where
+    T: ?Sized + SerializePartial<'a> + JsonSchema,
+    F: SerializeFilter<T>,
+{
+    /// Generates the JSON Schema of the value type, only listing the properties selected by the filter.
+    pub fn json_schema(&self) -> RootSchema {
+        self.json_schema_with(&mut SchemaGenerator::default())
+    }
+
+    /// Same as [`json_schema`][Partial::json_schema] but uses the provided generator.
+    pub fn json_schema_with(&self, gen: &mut SchemaGenerator) -> RootSchema {
+        let mut schema = gen.root_schema_for::<T>();
+        if let Some(object) = &mut schema.schema.object {
+            let names = T::field_names();
+            let filter = &self.filter;
+            // only known fields are checked, since filters are allowed to panic on unknown ones
+            let skip = |name: &str| names.contains(&name) && filter.skip(Field::new(name));
+
+            object.properties.retain(|name, _| !skip(name));
+            object.required.retain(|name| !skip(name));
+        }
+        schema
+    }
+}
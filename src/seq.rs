@@ -0,0 +1,38 @@
+use core::marker::PhantomData;
+
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::{Filtered, SerializeFilter};
+
+pub(crate) struct PartialSerializeSeq<'a, S, T, F>
+where
+    S: Serializer,
+    T: ?Sized,
+    F: ?Sized,
+{
+    pub(crate) ss: S::SerializeSeq,
+    pub(crate) filter: &'a F,
+    pub(crate) _ty: PhantomData<T>,
+}
+
+impl<'a, S, T, F> SerializeSeq for PartialSerializeSeq<'a, S, T, F>
+where
+    S: Serializer,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+{
+    type Ok = <S::SerializeSeq as SerializeSeq>::Ok;
+    type Error = <S::SerializeSeq as SerializeSeq>::Error;
+
+    fn serialize_element<TT>(&mut self, value: &TT) -> Result<(), Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        self.ss
+            .serialize_element(&Filtered::<_, T, F>::new(value, self.filter))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ss.end()
+    }
+}
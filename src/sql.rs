@@ -0,0 +1,191 @@
+//! SQL integration
+
+use alloc::string::String;
+
+use crate::{Field, SerializeFilter, SerializePartial};
+
+/// Description of a serializable field and the SQL column it maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldDescription {
+    name: &'static str,
+    column: &'static str,
+}
+
+/// Trait implemented by types whose serializable fields map to SQL columns.
+///
+/// When using the derive macro with the `sql` feature enabled, this trait is implemented automatically
+/// and the column names default to the Rust field names.
+/// They can be overridden using the `#[serde_partial(sql_column = "...")]` attribute.
+pub trait SqlColumns {
+    /// Descriptions of all the serializable fields, in declaration order.
+    const COLUMNS: &'static [FieldDescription];
+}
+
+impl FieldDescription {
+    /// Creates a new field description.
+    ///
+    /// The name should be the serde field name and not the Rust field name.
+    pub const fn new(name: &'static str, column: &'static str) -> Self {
+        Self { name, column }
+    }
+
+    /// Returns the serde field name.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the SQL column name.
+    pub const fn column(&self) -> &'static str {
+        self.column
+    }
+}
+
+/// Returns a comma separated list of the SQL columns selected by the filter.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{sql::fields_to_sql_columns, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+///     #[serde_partial(sql_column = "email_address")]
+///     email: &'static str,
+/// }
+///
+/// const USER: User = User {
+///     full_name: "John Doe",
+///     age: 42,
+///     email: "john.doe@example.com",
+/// };
+///
+/// let filtered = USER.with_fields(|u| [u.full_name, u.email]);
+/// assert_eq!(fields_to_sql_columns::<User>(&filtered.filter), "full_name, email_address");
+/// ```
+pub fn fields_to_sql_columns<'a, T>(filter: &T::Filter) -> String
+where
+    T: ?Sized + SerializePartial<'a> + SqlColumns,
+{
+    let mut columns = String::new();
+    for description in T::COLUMNS {
+        if filter.skip(Field::new(description.name)) {
+            continue;
+        }
+        if !columns.is_empty() {
+            columns.push_str(", ");
+        }
+        columns.push_str(description.column);
+    }
+    columns
+}
+
+/// Returns a comma separated SQL select list of the provided fields, optionally qualified by a table alias.
+///
+/// The serde names of the fields are used as column names. See [`fields_to_sql_select_with`] to use other column names.
+///
+/// ## Security
+///
+/// The table alias and column names are identifiers, not expressions.
+/// Those which aren't made of ASCII letters, digits and underscores, or which start with a digit, are quoted with double quotes, doubling the ones they contain,
+/// so requested field names can't inject SQL. Quoted identifiers are case sensitive in most databases.
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{sql::fields_to_sql_select, Field, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+///     email: &'static str,
+/// }
+///
+/// let fields = <User as SerializePartial>::Fields::FIELDS;
+/// assert_eq!(
+///     fields_to_sql_select([fields.full_name, fields.age], Some("u")),
+///     "u.full_name, u.age"
+/// );
+/// assert_eq!(fields_to_sql_select([fields.email], None), "email");
+///
+/// let requested = Field::<User>::new("email; DROP TABLE users");
+/// assert_eq!(fields_to_sql_select([requested], Some("u")), r#"u."email; DROP TABLE users""#);
+/// ```
+pub fn fields_to_sql_select<'a, T, I>(fields: I, table_alias: Option<&str>) -> String
+where
+    T: ?Sized,
+    I: IntoIterator<Item = Field<'a, T>>,
+{
+    fields_to_sql_select_with(fields, table_alias, |field| field.name())
+}
+
+/// Same as [`fields_to_sql_select`] but uses the provided function to get the column name of each field.
+///
+/// The column names are quoted the same way, see [the security notes][fields_to_sql_select#security].
+///
+/// ## Example
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_partial::{sql::fields_to_sql_select_with, SerializePartial};
+///
+/// #[derive(Serialize, SerializePartial)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     full_name: &'static str,
+///     age: u8,
+/// }
+///
+/// let fields = <User as SerializePartial>::Fields::FIELDS;
+/// let select = fields_to_sql_select_with([fields.full_name, fields.age], Some("u"), |f| {
+///     f.rust_name().unwrap_or(f.name())
+/// });
+/// assert_eq!(select, "u.full_name, u.age");
+/// ```
+pub fn fields_to_sql_select_with<'a, T, I, C, S>(
+    fields: I,
+    table_alias: Option<&str>,
+    mut column: C,
+) -> String
+where
+    T: ?Sized,
+    I: IntoIterator<Item = Field<'a, T>>,
+    C: FnMut(Field<'a, T>) -> S,
+    S: AsRef<str>,
+{
+    let mut select = String::new();
+    for field in fields {
+        if !select.is_empty() {
+            select.push_str(", ");
+        }
+        if let Some(alias) = table_alias {
+            push_identifier(&mut select, alias);
+            select.push('.');
+        }
+        push_identifier(&mut select, column(field).as_ref());
+    }
+    select
+}
+
+/// Appends an SQL identifier, quoting it unless it is made of ASCII letters, digits and underscores and doesn't start with a digit.
+fn push_identifier(sql: &mut String, identifier: &str) {
+    let plain = identifier
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        sql.push_str(identifier);
+    } else {
+        sql.push('"');
+        sql.push_str(&identifier.replace('"', "\"\""));
+        sql.push('"');
+    }
+}
@@ -2,14 +2,21 @@ use core::{
     hash::{BuildHasher, Hash},
     iter::Map,
 };
-use std::collections::{
-    hash_map::{HashMap, Keys},
-    HashSet,
+use std::{
+    collections::{
+        hash_map::{HashMap, Keys},
+        HashSet,
+    },
+    string::String,
+    vec::Vec,
 };
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
-use crate::{Field, Partial, SerializeFilter, SerializePartial};
+use crate::{
+    key::{KeyPartial, SerializeKeyPartial},
+    Field, Partial, SerializeFilter, SerializePartial, Sorted, UnknownFieldError,
+};
 
 impl<'a, K, V, S> SerializePartial<'a> for HashMap<K, V, S>
 where
@@ -27,7 +34,13 @@ where
         I: IntoIterator<Item = Field<'a, Self>>,
     {
         let fields: Self::Fields = self.keys().map(|k| Field::new(k.as_ref()));
-        let filter = select(fields).into_iter().collect();
+        let selected: Self::Filter = select(fields).into_iter().collect();
+        // fields which don't match any key would make the filtered length too large
+        let filter = self
+            .keys()
+            .map(|k| Field::new(k.as_ref()))
+            .filter(|f| selected.contains(f))
+            .collect();
         Partial {
             value: self,
             filter,
@@ -35,6 +48,53 @@ where
     }
 }
 
+impl<'a, K, V, S, F> Partial<'a, HashMap<K, V, S>, F>
+where
+    K: Hash + Eq + AsRef<str> + Serialize + 'a,
+    V: Serialize + 'a,
+    S: BuildHasher + Default + 'a,
+{
+    /// Returns a value which serializes the retained entries sorted by key,
+    /// making the output deterministic.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde_partial::{Field, SerializePartial};
+    ///
+    /// let map = HashMap::from([("c", 3), ("a", 1), ("b", 2), ("d", 4)]);
+    /// let filtered = map.with_fields(|_| [Field::new("d"), Field::new("b"), Field::new("a")]);
+    /// let json = serde_json::to_string(&filtered.sorted()).unwrap();
+    /// assert_eq!(json, r#"{"a":1,"b":2,"d":4}"#);
+    /// ```
+    pub fn sorted(self) -> Sorted<Self> {
+        Sorted { partial: self }
+    }
+}
+
+impl<'a, K, V, S, F> Serialize for Sorted<Partial<'a, HashMap<K, V, S>, F>>
+where
+    K: Hash + Ord + AsRef<str> + Serialize + 'a,
+    V: Serialize + 'a,
+    S: BuildHasher + Default + 'a,
+    F: SerializeFilter<HashMap<K, V, S>>,
+{
+    fn serialize<S2>(&self, serializer: S2) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        let Partial { value, filter } = &self.partial;
+        let human_readable = serializer.is_human_readable();
+        let mut entries: Vec<(&K, &V)> = value
+            .iter()
+            .filter(|(k, _)| !filter.skip_hr(Field::new(k.as_ref()), human_readable))
+            .collect();
+        entries.sort_unstable_by_key(|(k, _)| *k);
+        serializer.collect_map(entries)
+    }
+}
+
 impl<'a, K, V, S> SerializeFilter<HashMap<K, V, S>> for HashSet<Field<'a, HashMap<K, V, S>>, S>
 where
     S: BuildHasher,
@@ -48,11 +108,80 @@ where
     }
 }
 
+impl<'a, K, V, S> SerializeKeyPartial<'a> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Serialize + 'a,
+    V: Serialize + 'a,
+    S: BuildHasher + Default + 'a,
+{
+    type Key = K;
+    type Keys = Keys<'a, K, V>;
+    type KeyFilter = HashSet<K, S>;
+
+    fn with_key_fields<F, I>(&'a self, select: F) -> KeyPartial<'a, Self>
+    where
+        F: FnOnce(Self::Keys) -> I,
+        I: IntoIterator<Item = Self::Key>,
+    {
+        let filter = select(self.keys()).into_iter().collect();
+        KeyPartial {
+            value: self,
+            filter,
+        }
+    }
+
+    fn serialize_key_partial<S2>(
+        &self,
+        filter: &Self::KeyFilter,
+        serializer: S2,
+    ) -> Result<S2::Ok, S2::Error>
+    where
+        S2: Serializer,
+    {
+        serializer.collect_map(self.iter().filter(|(k, _)| filter.contains(k)))
+    }
+}
+
+impl<T, S> SerializeFilter<T> for HashSet<&str, S>
+where
+    T: ?Sized,
+    S: BuildHasher,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        !self.contains(field.name())
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        // the names don't necessarily match fields of `T`
+        None
+    }
+}
+
+impl<T, S> SerializeFilter<T> for HashSet<String, S>
+where
+    T: ?Sized,
+    S: BuildHasher,
+{
+    fn skip(&self, field: Field<'_, T>) -> bool {
+        !self.contains(field.name())
+    }
+
+    fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+        // the names don't necessarily match fields of `T`
+        None
+    }
+}
+
+impl std::error::Error for UnknownFieldError<'_> {}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Field, SerializePartial};
+    use crate::{Field, Partial, SerializePartial};
 
-    use std::collections::HashMap;
+    use std::{
+        collections::{HashMap, HashSet},
+        string::String,
+    };
 
     #[test]
     fn hash_map() {
@@ -63,4 +192,51 @@ mod tests {
             serde_json::json!({ "a": "b" })
         )
     }
+
+    #[test]
+    fn name_sets() {
+        let map = HashMap::from([("a", "b"), ("c", "d")]);
+
+        let filter = HashSet::from(["a"]);
+        assert_eq!(
+            serde_json::to_value(&Partial {
+                value: &map,
+                filter
+            })
+            .unwrap(),
+            serde_json::json!({ "a": "b" })
+        );
+
+        let filter: HashSet<String> = serde_json::from_str(r#"["c"]"#).unwrap();
+        assert_eq!(
+            serde_json::to_value(&Partial {
+                value: &map,
+                filter
+            })
+            .unwrap(),
+            serde_json::json!({ "c": "d" })
+        );
+
+        let filter = HashSet::from(["a", "e"]);
+        assert_eq!(
+            Partial {
+                value: &map,
+                filter
+            }
+            .len(),
+            None
+        );
+    }
+
+    #[test]
+    fn hash_map_keys() {
+        use crate::key::SerializeKeyPartial;
+
+        let map = HashMap::from([(1, "a"), (2, "b")]);
+        let filtered = map.with_key_fields(|_| [2]);
+        assert_eq!(
+            serde_json::to_value(&filtered).unwrap(),
+            serde_json::json!({ "2": "b" })
+        );
+    }
 }
@@ -1,40 +1,133 @@
 use core::marker::PhantomData;
 
-use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
 
-use crate::{Field, SerializeFilter, SerializePartial};
+use crate::{filter::ErasedFilter, Field, Filtered, SerializeFilter};
+
+/// Filtering state shared by structs and struct variants.
+pub(crate) struct FieldCursor<'a, T, F>
+where
+    T: ?Sized,
+    F: ?Sized,
+{
+    filter: &'a F,
+    human_readable: bool,
+    /// Number of fields reported by the filter.
+    len: Option<usize>,
+    /// Number of selected fields which haven't been serialized yet, if the filter's length is exact.
+    ///
+    /// Once it reaches zero, the remaining fields are skipped without consulting the filter.
+    remaining: Option<usize>,
+    /// Position of the next field, counting both serialized and skipped fields.
+    position: usize,
+    _ty: PhantomData<T>,
+}
+
+impl<'a, T, F> FieldCursor<'a, T, F>
+where
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+{
+    pub(crate) fn new(filter: &'a F, len: usize, human_readable: bool) -> Self {
+        let len = filter.filtered_len_hr(Some(len), human_readable);
+        Self {
+            filter,
+            human_readable,
+            len,
+            remaining: len.filter(|_| filter.exact_len()),
+            position: 0,
+            _ty: PhantomData,
+        }
+    }
+
+    /// Returns the length to report to the serializer.
+    pub(crate) fn len(&self, len: usize) -> usize {
+        self.len.unwrap_or(len)
+    }
+
+    /// Advances past a field, returning its value along with its nested filter if it is selected.
+    fn select<'v, V>(&mut self, key: &'static str, value: &'v V) -> Option<Selected<'v, V>>
+    where
+        'a: 'v,
+        V: ?Sized,
+    {
+        let position = self.position;
+        self.position += 1;
+        if self.remaining == Some(0) {
+            // all the selected fields have already been serialized
+            return None;
+        }
+        if self
+            .filter
+            .skip_at(Field::new(key), position, self.human_readable)
+        {
+            return None;
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        Some(Selected {
+            value,
+            filter: self.filter.nested_hr(Field::new(key), self.human_readable),
+        })
+    }
+
+    /// Advances past a field skipped by serde.
+    fn skip(&mut self) {
+        self.position += 1;
+    }
+}
+
+/// The value of a selected field, with the filter to apply to it if any.
+struct Selected<'a, V>
+where
+    V: ?Sized,
+{
+    value: &'a V,
+    filter: Option<&'a dyn ErasedFilter>,
+}
+
+impl<V> Serialize for Selected<'_, V>
+where
+    V: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.filter {
+            Some(filter) => Filtered::<_, (), _>::new(self.value, filter).serialize(serializer),
+            None => self.value.serialize(serializer),
+        }
+    }
+}
 
 pub(crate) struct PartialSerializeStruct<'a, S, T, F>
 where
     S: Serializer,
     T: ?Sized,
+    F: ?Sized,
 {
     pub(crate) ss: S::SerializeStruct,
-    pub(crate) filter: &'a F,
-    pub(crate) _ty: PhantomData<T>,
+    pub(crate) cursor: FieldCursor<'a, T, F>,
 }
 
 impl<'a, S, T, F> SerializeStruct for PartialSerializeStruct<'a, S, T, F>
 where
     S: Serializer,
-    T: ?Sized + for<'p> SerializePartial<'p>,
-    F: SerializeFilter<T>,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
 {
     type Ok = <S::SerializeStruct as SerializeStruct>::Ok;
     type Error = <S::SerializeStruct as SerializeStruct>::Error;
 
-    fn serialize_field<TT: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &TT,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<TT>(&mut self, key: &'static str, value: &TT) -> Result<(), Self::Error>
     where
-        TT: Serialize,
+        TT: ?Sized + Serialize,
     {
-        if self.filter.skip(Field::new(key)) {
-            self.skip_field(key)
-        } else {
-            self.ss.serialize_field(key, value)
+        match self.cursor.select(key, value) {
+            Some(selected) => self.ss.serialize_field(key, &selected),
+            None => self.ss.skip_field(key),
         }
     }
 
@@ -43,6 +136,183 @@ where
     }
 
     fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.cursor.skip();
         self.ss.skip_field(key)
     }
 }
+
+pub(crate) struct PartialSerializeStructVariant<'a, S, T, F>
+where
+    S: Serializer,
+    T: ?Sized,
+    F: ?Sized,
+{
+    pub(crate) ssv: S::SerializeStructVariant,
+    pub(crate) cursor: FieldCursor<'a, T, F>,
+}
+
+impl<'a, S, T, F> SerializeStructVariant for PartialSerializeStructVariant<'a, S, T, F>
+where
+    S: Serializer,
+    T: ?Sized,
+    F: ?Sized + SerializeFilter<T>,
+{
+    type Ok = <S::SerializeStructVariant as SerializeStructVariant>::Ok;
+    type Error = <S::SerializeStructVariant as SerializeStructVariant>::Error;
+
+    fn serialize_field<TT>(&mut self, key: &'static str, value: &TT) -> Result<(), Self::Error>
+    where
+        TT: ?Sized + Serialize,
+    {
+        match self.cursor.select(key, value) {
+            Some(selected) => self.ssv.serialize_field(key, &selected),
+            None => self.ssv.skip_field(key),
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ssv.end()
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.cursor.skip();
+        self.ssv.skip_field(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, marker::PhantomData};
+
+    use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
+
+    use crate::{Field, PartialSerializer, SerializeFilter};
+
+    struct Point {
+        x: i32,
+        y: i32,
+        z: i32,
+    }
+
+    impl Serialize for Point {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut ss = serializer.serialize_struct("Point", 3)?;
+            ss.serialize_field("x", &self.x)?;
+            ss.serialize_field("y", &self.y)?;
+            ss.serialize_field("z", &self.z)?;
+            ss.end()
+        }
+    }
+
+    struct OnlyX {
+        calls: Cell<usize>,
+    }
+
+    impl SerializeFilter<Point> for OnlyX {
+        fn skip(&self, field: Field<'_, Point>) -> bool {
+            self.calls.set(self.calls.get() + 1);
+            field.name() != "x"
+        }
+
+        fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+            Some(1)
+        }
+
+        fn exact_len(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn short_circuit() {
+        let filter = OnlyX {
+            calls: Cell::new(0),
+        };
+        let mut buf = [0; 16];
+        let mut json = &mut buf[..];
+        let serializer = PartialSerializer {
+            s: &mut serde_json::Serializer::new(&mut json),
+            filter: &filter,
+            _ty: PhantomData,
+        };
+        Point { x: 1, y: 2, z: 3 }.serialize(serializer).unwrap();
+
+        let written = 16 - json.len();
+        assert_eq!(&buf[..written], br#"{"x":1}"#);
+        assert_eq!(filter.calls.get(), 1);
+    }
+
+    enum Shape {
+        Point { x: i32, y: i32 },
+    }
+
+    impl Serialize for Shape {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let Shape::Point { x, y } = self;
+            let mut ssv = serializer.serialize_struct_variant("Shape", 0, "Point", 2)?;
+            ssv.serialize_field("x", x)?;
+            ssv.serialize_field("y", y)?;
+            ssv.end()
+        }
+    }
+
+    struct OnlyY;
+
+    impl SerializeFilter<Shape> for OnlyY {
+        fn skip(&self, field: Field<'_, Shape>) -> bool {
+            field.name() != "y"
+        }
+
+        fn filtered_len(&self, _len: Option<usize>) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn struct_variant() {
+        let shape = Shape::Point { x: 1, y: 2 };
+        let partial = crate::Filtered::<_, Shape, _>::new(&shape, &OnlyY);
+
+        serde_test::assert_ser_tokens(
+            &serde_test::Configure::compact(partial),
+            &[
+                serde_test::Token::StructVariant {
+                    name: "Shape",
+                    variant: "Point",
+                    len: 1,
+                },
+                serde_test::Token::Str("y"),
+                serde_test::Token::I32(2),
+                serde_test::Token::StructVariantEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn length_hint() {
+        let filter = OnlyX {
+            calls: Cell::new(0),
+        };
+        let point = Point { x: 1, y: 2, z: 3 };
+        let partial = crate::Filtered::<_, Point, _>::new(&point, &filter);
+
+        serde_test::assert_ser_tokens(
+            &serde_test::Configure::compact(partial),
+            &[
+                serde_test::Token::Struct {
+                    name: "Point",
+                    len: 1,
+                },
+                serde_test::Token::Str("x"),
+                serde_test::Token::I32(1),
+                serde_test::Token::StructEnd,
+            ],
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! CBOR round trips, which need a newer toolchain than the minimum supported one.
+#![cfg(serde_partial_cbor)]
+
+use serde::{Deserialize, Serialize};
+use serde_partial::SerializePartial;
+
+#[derive(Serialize, SerializePartial)]
+struct User {
+    name: &'static str,
+    age: u8,
+    email: &'static str,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PublicUser {
+    name: String,
+    age: u8,
+}
+
+const USER: User = User {
+    name: "John Doe",
+    age: 42,
+    email: "john.doe@example.com",
+};
+
+#[test]
+fn round_trip() {
+    let filtered = USER.with_fields(|u| [u.name, u.age]);
+
+    let mut cbor = Vec::new();
+    ciborium::ser::into_writer(&filtered, &mut cbor).unwrap();
+    let decoded: PublicUser = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+    assert_eq!(
+        decoded,
+        PublicUser {
+            name: "John Doe".to_owned(),
+            age: 42,
+        }
+    );
+}
+
+#[test]
+fn round_trip_without_fields() {
+    let filtered = USER.without_fields(|u| [u.email]);
+
+    let mut cbor = Vec::new();
+    ciborium::ser::into_writer(&filtered, &mut cbor).unwrap();
+    let decoded: PublicUser = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+    assert_eq!(
+        decoded,
+        PublicUser {
+            name: "John Doe".to_owned(),
+            age: 42,
+        }
+    );
+}
+
+#[test]
+fn round_trip_chained() {
+    let requested = USER.with_fields(|u| [u.age, u.email]).filter;
+    let filtered = USER.with_fields(|u| [u.name, u.age]).chain_or(requested);
+
+    let mut cbor = Vec::new();
+    ciborium::ser::into_writer(&filtered, &mut cbor).unwrap();
+    let decoded: ciborium::value::Value = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+    assert_eq!(decoded.as_map().map(Vec::len), Some(3));
+}
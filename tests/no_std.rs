@@ -0,0 +1,282 @@
+//! Partial serialization without `std` nor `alloc`
+//!
+//! Run with `cargo test --no-default-features --test no_std` to also check the no-alloc configuration.
+
+#![no_std]
+
+use core::fmt;
+
+use serde::{
+    ser::{Impossible, SerializeStruct, Serializer},
+    Serialize,
+};
+use serde_partial::{
+    filter::{ArrayFilter, EmptyFilter, IdentityFilter, InverseFilter},
+    Field, Partial, SerializeFilter, SerializePartial,
+};
+
+#[derive(Serialize, SerializePartial)]
+struct User {
+    name: &'static str,
+    age: u8,
+    email: &'static str,
+}
+
+const USER: User = User {
+    name: "John Doe",
+    age: 42,
+    email: "john.doe@example.com",
+};
+
+#[derive(Serialize, SerializePartial)]
+struct Secret {
+    #[serde_partial(always)]
+    id: u32,
+    #[serde_partial(never)]
+    token: &'static str,
+    label: &'static str,
+    note: &'static str,
+}
+
+const SECRET: Secret = Secret {
+    id: 1,
+    token: "hunter2",
+    label: "label",
+    note: "note",
+};
+
+/// Records the names of the serialized struct fields in a fixed size buffer.
+#[derive(Default)]
+struct Names {
+    names: [&'static str; 8],
+    len: usize,
+    skipped: usize,
+    hint: usize,
+}
+
+impl Names {
+    fn collect<T: Serialize>(value: &T) -> Self {
+        let mut names = Names::default();
+        value.serialize(&mut names).unwrap();
+        names
+    }
+
+    fn as_slice(&self) -> &[&'static str] {
+        &self.names[..self.len]
+    }
+}
+
+macro_rules! unsupported {
+    ($($method:ident($($ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<$ret, fmt::Error> {
+                Err(fmt::Error)
+            }
+        )*
+    };
+}
+
+impl Serializer for &mut Names {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    type SerializeSeq = Impossible<(), fmt::Error>;
+    type SerializeTuple = Impossible<(), fmt::Error>;
+    type SerializeTupleStruct = Impossible<(), fmt::Error>;
+    type SerializeTupleVariant = Impossible<(), fmt::Error>;
+    type SerializeMap = Impossible<(), fmt::Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), fmt::Error>;
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self, fmt::Error> {
+        self.hint = len;
+        Ok(self)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<(), fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    unsupported! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn collect_str<T: ?Sized + fmt::Display>(self, _value: &T) -> Result<(), fmt::Error> {
+        Err(fmt::Error)
+    }
+}
+
+impl SerializeStruct for &mut Names {
+    type Ok = ();
+    type Error = fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        _value: &T,
+    ) -> Result<(), fmt::Error> {
+        let slot = self.names.get_mut(self.len).ok_or(fmt::Error)?;
+        *slot = key;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), fmt::Error> {
+        self.skipped += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn derived_filter() {
+    let names = Names::collect(&USER.with_fields(|u| [u.name, u.email]));
+    assert_eq!(names.as_slice(), ["name", "email"]);
+    assert_eq!(names.skipped, 1);
+
+    let names = Names::collect(&USER.without_fields(|u| [u.name, u.email]));
+    assert_eq!(names.as_slice(), ["age"]);
+    assert_eq!(names.skipped, 2);
+}
+
+#[test]
+fn array_filter() {
+    let fields = <User as SerializePartial>::Fields::FIELDS;
+    let partial = Partial {
+        value: &USER,
+        filter: ArrayFilter::new([fields.age]),
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["age"]);
+
+    let partial = Partial {
+        value: &USER,
+        filter: InverseFilter::<User, _>::new(ArrayFilter::new([fields.age])),
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["name", "email"]);
+}
+
+#[test]
+fn inverse_unknown_name() {
+    let partial = Partial {
+        value: &USER,
+        filter: InverseFilter::<User, _>::new(ArrayFilter::new([
+            Field::new("name"),
+            Field::new("bogus"),
+        ])),
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["age", "email"]);
+    assert_eq!(names.hint, 3);
+}
+
+#[test]
+fn inverse_len() {
+    let names = Names::collect(&USER.without_fields(|u| [u.name]));
+    assert_eq!(names.as_slice(), ["age", "email"]);
+    assert_eq!(names.hint, 2);
+
+    let names = Names::collect(&SECRET.without_fields(|s| [s.id, s.label]));
+    assert_eq!(names.as_slice(), ["id", "note"]);
+    assert_eq!(names.hint, 2);
+
+    let partial = Partial {
+        value: &SECRET,
+        filter: InverseFilter::<Secret, _>::new(EmptyFilter),
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["id", "label", "note"]);
+    assert_eq!(names.hint, 3);
+}
+
+#[test]
+fn marked_fields() {
+    let partial = Partial {
+        value: &SECRET,
+        filter: IdentityFilter,
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["id", "label", "note"]);
+    assert_eq!(names.hint, 3);
+    assert_eq!(partial.len(), Some(3));
+
+    let partial = Partial {
+        value: &SECRET,
+        filter: ArrayFilter::new([Field::new("token"), Field::new("label")]),
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["id", "label"]);
+}
+
+#[test]
+fn fallback_filter() {
+    let partial = USER.with_fields(|u| [u.age]).or_fallback(IdentityFilter);
+    assert!(partial.filter.exact_len());
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), ["age"]);
+    assert_eq!(names.hint, 1);
+}
+
+#[test]
+fn empty_filter() {
+    let partial = Partial {
+        value: &USER,
+        filter: EmptyFilter,
+    };
+    let names = Names::collect(&partial);
+    assert_eq!(names.as_slice(), [] as [&str; 0]);
+    assert_eq!(names.skipped, 3);
+}
+
+#[test]
+#[should_panic(
+    expected = "unknown field `phone`, expected one of `name`, `age`, `email` in `no_std::User`"
+)]
+fn unknown_field() {
+    let _ = USER.with_fields(|_| [Field::new("phone")]);
+}